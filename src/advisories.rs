@@ -0,0 +1,362 @@
+use crate::{
+    cargo::lowest_version,
+    config::{data_dir, Advisories},
+};
+use anyhow::{anyhow, Result};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+const ADVISORY_DB_URL: &str = "https://github.com/rustsec/advisory-db.git";
+
+/// Advisories from a local clone of the RustSec advisory-db, keyed by crate
+#[derive(Default, Debug)]
+pub struct AdvisoryDb {
+    crates: HashMap<String, Vec<Advisory>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub id: String,
+    pub title: String,
+    pub date: String,
+    /// Set for notices like `unmaintained` that aren't vulnerabilities
+    pub informational: Option<String>,
+    patched: Vec<VersionReq>,
+    unaffected: Vec<VersionReq>,
+}
+
+impl Advisory {
+    pub fn affects(&self, version: &Version) -> bool {
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|req| req.matches(version))
+    }
+    pub fn is_vulnerability(&self) -> bool {
+        self.informational.is_none()
+    }
+    pub fn patched(&self) -> String {
+        match self.patched.is_empty() {
+            true => "no patched versions".into(),
+            false => self
+                .patched
+                .iter()
+                .map(|req| req.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+impl AdvisoryDb {
+    /// Reads every advisory under `crates/` in the database directory, which is
+    /// empty if it hasn't been downloaded yet
+    pub fn load(config: &Advisories) -> Result<Self> {
+        let mut db = Self::default();
+        let path = db_path(config)?;
+        if !path.exists() {
+            return Ok(db);
+        }
+        let dir = path.join("crates");
+        let crate_dirs = fs::read_dir(&dir)
+            .map_err(|e| anyhow!("Unable to read advisories from {}: {e}", dir.display()))?;
+        // Anything unreadable or that fails to parse is skipped rather than losing the rest
+        let files = crate_dirs
+            .flatten()
+            .filter_map(|crate_dir| fs::read_dir(crate_dir.path()).ok())
+            .flat_map(|files| files.flatten());
+        for file in files {
+            let advisory = fs::read_to_string(file.path())
+                .ok()
+                .map(|text| parse(&text));
+            if let Some(Ok((package, advisory))) = advisory {
+                db.crates.entry(package).or_default().push(advisory);
+            }
+        }
+        for advisories in db.crates.values_mut() {
+            advisories.sort_by(|a, b| b.date.cmp(&a.date));
+        }
+        Ok(db)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crates.is_empty()
+    }
+
+    /// Every advisory for the crate, newest first
+    pub fn for_crate(&self, id: &str) -> &[Advisory] {
+        self.crates
+            .get(id)
+            .map(|a| a.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Advisories affecting a version, which can also be a requirement like `1.2`
+    pub fn affecting(&self, id: &str, version: &str) -> Vec<&Advisory> {
+        let Some(version) = lowest_version(version) else {
+            return vec![];
+        };
+        self.for_crate(id)
+            .iter()
+            .filter(|a| a.affects(&version))
+            .collect()
+    }
+
+    /// A warning for adding a version that has known vulnerabilities
+    pub fn add_warning(&self, id: &str, version: &str) -> Option<String> {
+        let ids: Vec<&str> = self
+            .affecting(id, version)
+            .into_iter()
+            .filter(|a| a.is_vulnerability())
+            .map(|a| a.id.as_str())
+            .collect();
+        match ids.is_empty() {
+            true => None,
+            false => Some(format!("{id} {version} is affected by {}", ids.join(", "))),
+        }
+    }
+}
+
+/// Clones the database if it's missing, otherwise pulls the latest advisories
+pub fn refresh(config: &Advisories) -> Result<AdvisoryDb> {
+    let path = db_path(config)?;
+    let mut git = Command::new("git");
+    if path.exists() {
+        git.args(["pull", "--ff-only", "-q"]).current_dir(&path);
+    } else {
+        git.args(["clone", "--depth", "1", "-q", ADVISORY_DB_URL])
+            .arg(&path);
+    }
+    // This runs behind the UI, so git can't print over it or wait on a prompt
+    let output = git
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| anyhow!("Unable to run git: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().map(str::trim).rfind(|l| !l.is_empty());
+        return Err(match reason {
+            Some(reason) => anyhow!("Unable to update the advisory database: {reason}"),
+            None => anyhow!("Unable to update the advisory database"),
+        });
+    }
+    AdvisoryDb::load(config)
+}
+
+fn db_path(config: &Advisories) -> Result<PathBuf> {
+    match &config.path {
+        Some(path) => Ok(path.to_owned()),
+        None => Ok(data_dir()?.join("advisory-db")),
+    }
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: Versions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    package: String,
+    date: String,
+    /// Only in the older toml format, markdown advisories have it as a heading
+    title: Option<String>,
+    informational: Option<String>,
+    withdrawn: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct Versions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+/// Parses an advisory in either the markdown format with a toml block or plain toml
+fn parse(text: &str) -> Result<(String, Advisory)> {
+    let (front_matter, body) = match text.trim_start().strip_prefix("```toml") {
+        Some(rest) => rest
+            .split_once("```")
+            .ok_or_else(|| anyhow!("Unterminated toml block"))?,
+        None => (text, ""),
+    };
+    let file: AdvisoryFile = toml::from_str(front_matter)?;
+    if file.advisory.withdrawn.is_some() {
+        return Err(anyhow!("Advisory was withdrawn"));
+    }
+
+    let title = body
+        .lines()
+        .find_map(|l| l.strip_prefix("# "))
+        .map(|t| t.trim().to_owned())
+        .or(file.advisory.title)
+        .unwrap_or_default();
+    let reqs = |reqs: Vec<String>| {
+        reqs.iter()
+            .filter_map(|r| VersionReq::parse(r).ok())
+            .collect()
+    };
+
+    Ok((
+        file.advisory.package,
+        Advisory {
+            id: file.advisory.id,
+            title,
+            date: file.advisory.date,
+            informational: file.advisory.informational,
+            patched: reqs(file.versions.patched),
+            unaffected: reqs(file.versions.unaffected),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKDOWN: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2021-0003"
+package = "smallvec"
+date = "2021-01-08"
+url = "https://github.com/servo/rust-smallvec/issues/252"
+categories = ["memory-corruption"]
+
+[versions]
+patched = [">= 0.6.14, < 1.0.0", ">= 1.6.1"]
+unaffected = ["< 0.6.3"]
+```
+
+# Buffer overflow in SmallVec::insert_many
+
+A bug in the `SmallVec::insert_many` method caused it to allocate a buffer
+that was smaller than needed.
+"#;
+
+    const TOML: &str = r#"
+[advisory]
+id = "RUSTSEC-2020-0016"
+package = "net2"
+title = "`net2` crate has been deprecated; use `socket2` instead"
+date = "2020-05-01"
+informational = "unmaintained"
+
+[versions]
+patched = []
+"#;
+
+    fn version(v: &str) -> Version {
+        Version::parse(v).unwrap()
+    }
+
+    #[test]
+    fn parses_markdown_advisories() {
+        let (package, advisory) = parse(MARKDOWN).unwrap();
+        assert_eq!(package, "smallvec");
+        assert_eq!(advisory.id, "RUSTSEC-2021-0003");
+        assert_eq!(advisory.title, "Buffer overflow in SmallVec::insert_many");
+        assert_eq!(advisory.date, "2021-01-08");
+        assert!(advisory.is_vulnerability());
+        assert_eq!(advisory.patched(), ">=0.6.14, <1.0.0, >=1.6.1");
+
+        let cases = [
+            ("0.6.2", false),
+            ("0.6.3", true),
+            ("0.6.13", true),
+            ("0.6.14", false),
+            ("1.0.0", true),
+            ("1.6.0", true),
+            ("1.6.1", false),
+            ("2.0.0", false),
+        ];
+        for (v, affected) in cases {
+            assert_eq!(advisory.affects(&version(v)), affected, "{v}");
+        }
+    }
+
+    #[test]
+    fn parses_toml_advisories() {
+        let (package, advisory) = parse(TOML).unwrap();
+        assert_eq!(package, "net2");
+        assert_eq!(advisory.id, "RUSTSEC-2020-0016");
+        assert_eq!(
+            advisory.title,
+            "`net2` crate has been deprecated; use `socket2` instead"
+        );
+        assert_eq!(advisory.informational.as_deref(), Some("unmaintained"));
+        assert!(!advisory.is_vulnerability());
+        assert_eq!(advisory.patched(), "no patched versions");
+        assert!(advisory.affects(&version("0.2.39")));
+    }
+
+    #[test]
+    fn parse_edge_cases() {
+        // Without a heading or a title in the toml there's no title
+        let untitled = MARKDOWN.replace("# Buffer", "Buffer");
+        assert_eq!(parse(&untitled).unwrap().1.title, "");
+        // Versions are optional and requirements that don't parse are dropped
+        let no_versions = TOML.replace("[versions]\npatched = []", "");
+        assert_eq!(
+            parse(&no_versions).unwrap().1.patched(),
+            "no patched versions"
+        );
+        let bad_req = MARKDOWN.replace(r#"">= 1.6.1""#, r#""not a req""#);
+        assert_eq!(parse(&bad_req).unwrap().1.patched(), ">=0.6.14, <1.0.0");
+
+        let withdrawn = TOML.replace("[versions]", "withdrawn = \"2020-06-01\"\n[versions]");
+        assert!(parse(&withdrawn).is_err());
+        let unterminated = MARKDOWN.replacen("```\n\n#", "\n#", 1);
+        assert!(parse(&unterminated).is_err());
+        assert!(parse(&TOML.replace("package", "crate")).is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn load_skips_bad_entries() {
+        let dir = std::env::temp_dir().join(format!("cratui-advisories-{}", std::process::id()));
+        let crates = dir.join("crates");
+        fs::create_dir_all(crates.join("smallvec")).unwrap();
+        fs::create_dir_all(crates.join("net2").join("not-a-file.md")).unwrap();
+        fs::write(
+            crates.join("smallvec").join("RUSTSEC-2021-0003.md"),
+            MARKDOWN,
+        )
+        .unwrap();
+        fs::write(
+            crates.join("smallvec").join("broken.md"),
+            "```toml\n[advisory",
+        )
+        .unwrap();
+        fs::write(crates.join("net2").join("RUSTSEC-2020-0016.toml"), TOML).unwrap();
+        fs::write(crates.join("README.md"), "Not a crate").unwrap();
+        let config = Advisories {
+            path: Some(dir.to_owned()),
+        };
+
+        let db = AdvisoryDb::load(&config).unwrap();
+        assert_eq!(db.for_crate("smallvec").len(), 1);
+        assert_eq!(db.for_crate("net2").len(), 1);
+        assert!(db.add_warning("smallvec", "1.6.0").is_some());
+        assert!(db.add_warning("net2", "0.2.39").is_none());
+
+        // A directory that isn't a database is an error, one that isn't there is empty
+        fs::remove_dir_all(&crates).unwrap();
+        assert!(AdvisoryDb::load(&config).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(AdvisoryDb::load(&config).unwrap().is_empty());
+    }
+}
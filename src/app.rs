@@ -1,35 +1,116 @@
 use crate::{
-    config::Config,
+    advisories::AdvisoryDb,
+    cargo::{self, search::Crate, ManifestEdit},
+    config::{Config, PolicyAction},
+    history::History,
+    info_cache::InfoCache,
+    keymap::{self, Action},
     pages::{
+        command_line::{self, CommandLine},
+        compare::{self, ComparePage},
+        details::{self, DetailsPage},
+        favourites::{self, FavouritesPage},
+        help::{self, HelpPage},
         manage::{self, ManagePage},
+        preview::{self, EditPreview},
         search::{self, SearchPage},
+        versions::{self, VersionPicker},
     },
-    ui::{draw_ui, rgb},
+    theme::Theme,
+    ui::draw_ui,
+    worker::{Request, Response, Worker},
 };
 use anyhow::{anyhow, Result};
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, MouseButton, MouseEvent, MouseEventKind};
 use std::time::{Duration, Instant};
-use tui::{backend::Backend, style::Color, Terminal};
+use tui::{backend::Backend, layout::Rect, style::Style, Terminal};
 
 const TAB_COUNT: u8 = 3;
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+/// How long the title flashes for warnings and errors
+const FLASH_TIME: Duration = Duration::from_millis(250);
 
 #[derive(Default)]
 pub struct App {
     pub config: Config,
+    pub theme: Theme,
+    pub history: History,
     pub tab: u8,
     pub warning_timer: Option<Instant>,
     pub error_timer: Option<Instant>,
+    pub tab_rects: Vec<Rect>,
+    /// Animation frames drawn so far
+    pub ticks: usize,
+    last_click: Option<(Instant, u16, u16)>,
+    pub advisories: AdvisoryDb,
+    pub info_cache: InfoCache,
+    /// The project's `rust-version`
+    pub rust_version: Option<semver::Version>,
+    /// Runs network requests and slow commands in the background
+    pub worker: Worker,
+    /// An add or version pick waiting on a crate's info
+    awaiting_info: Option<AwaitingInfo>,
+    /// The crates on screen when their info was last asked for
+    prefetched: Vec<String>,
+    pub debug_panel: bool,
+    /// Manifest changes made this session, newest last
+    undo: Vec<ManifestEdit>,
+    redo: Vec<ManifestEdit>,
 
+    pub help_page: HelpPage,
+    pub command_line: CommandLine,
+    pub details_page: DetailsPage,
+    pub compare_page: ComparePage,
+    pub version_picker: VersionPicker,
+    pub edit_preview: EditPreview,
     pub search_page: SearchPage,
     pub manage_page: ManagePage,
+    pub favourites_page: FavouritesPage,
+}
+
+/// Something waiting on a crate's info before it can go ahead
+#[derive(Debug)]
+enum AwaitingInfo {
+    Add {
+        id: String,
+        /// The newest compatible version is picked when there isn't one
+        version: Option<String>,
+        /// Used when the info can't be fetched
+        fallback: Option<String>,
+        features: Vec<String>,
+    },
+    Versions(String),
+}
+
+impl AwaitingInfo {
+    fn id(&self) -> &str {
+        match self {
+            AwaitingInfo::Add { id, .. } | AwaitingInfo::Versions(id) => id,
+        }
+    }
 }
 
 impl App {
     pub fn new(config: Config) -> Self {
-        Self {
-            config,
-            ..Default::default()
+        let mut app = Self::default();
+        match Theme::load(&config.appearance, config.colors.as_ref()) {
+            Ok(theme) => app.theme = theme,
+            Err(e) => {
+                app.error();
+                app.command_line.error(format!("{e}"));
+            }
+        }
+        if let Some(error) = config.policy.errors().first() {
+            app.error();
+            app.command_line.error(error.to_owned());
         }
+        app.history = History::load(config.search.history_size);
+        // Nothing is marked until the advisories are read
+        let path = config.advisories.path.to_owned();
+        app.worker.send(Request::LoadAdvisories(path));
+        app.rust_version = cargo::rust_version().ok().flatten();
+        app.config = config;
+        app
     }
     pub fn warn(&mut self) {
         self.warning_timer = Some(Instant::now());
@@ -38,28 +119,280 @@ impl App {
         self.error_timer = Some(Instant::now());
     }
     pub fn is_error(&self) -> bool {
-        if let Some(i) = self.error_timer {
-            if i.elapsed() < Duration::from_millis(250) {
-                return true;
+        self.error_timer.is_some_and(|i| i.elapsed() < FLASH_TIME)
+    }
+    pub fn is_warning(&self) -> bool {
+        self.warning_timer.is_some_and(|i| i.elapsed() < FLASH_TIME)
+    }
+    /// Whether anything on screen changes over time and needs redrawing each tick
+    fn is_animating(&self) -> bool {
+        self.is_error() || self.is_warning() || self.worker.is_busy() || self.debug_panel
+    }
+    pub fn open(&mut self, url: &str) {
+        open::that(url).unwrap_or_else(|_| self.error());
+    }
+    /// Records a left click and returns whether it completes a double click
+    pub fn is_double_click(&mut self, e: MouseEvent) -> bool {
+        let double_click = matches!(
+            self.last_click,
+            Some((time, column, row))
+                if time.elapsed() < DOUBLE_CLICK_TIME && column == e.column && row == e.row
+        );
+        self.last_click = if double_click {
+            None
+        } else {
+            Some((Instant::now(), e.column, e.row))
+        };
+        double_click
+    }
+    /// Updates the advisory database in the background
+    pub fn refresh_advisories(&mut self) {
+        let path = self.config.advisories.path.to_owned();
+        self.worker.send(Request::RefreshAdvisories(path));
+    }
+    /// Previews adding a crate once its info is in, which picks the version
+    /// when none is given and has the license the policy checks
+    pub fn add(&mut self, id: String, version: Option<String>, features: Vec<String>) {
+        self.after_info(AwaitingInfo::Add {
+            id,
+            version,
+            fallback: None,
+            features,
+        });
+    }
+    /// Previews adding a search result, which has a version to fall back on
+    /// if its info can't be fetched
+    pub fn add_crate(&mut self, c: Crate) {
+        // Crates.io leaves yanked versions out of these unless there's nothing else
+        let fallback = c.max_stable_version.or(Some(c.max_version));
+        self.after_info(AwaitingInfo::Add {
+            id: c.id,
+            version: None,
+            fallback: fallback.filter(|v| !v.is_empty()),
+            features: vec![],
+        });
+    }
+    /// Opens the version picker once the crate's versions are in
+    pub fn pick_version(&mut self, id: String) {
+        self.after_info(AwaitingInfo::Versions(id));
+    }
+    /// Goes ahead now if the info isn't needed or is already in, otherwise
+    /// fetches it ahead of anything else and waits
+    fn after_info(&mut self, awaiting: AwaitingInfo) {
+        let needs_info = match &awaiting {
+            AwaitingInfo::Add { version, .. } => {
+                version.is_none() || self.config.policy.is_enabled()
             }
+            AwaitingInfo::Versions(_) => true,
+        };
+        let id = awaiting.id();
+        if !needs_info || self.info_cache.contains(id) {
+            self.awaiting_info = None;
+            self.finish(awaiting);
+            return;
         }
-        false
+        self.worker.send(Request::Info(id.to_owned()));
+        self.command_line.info(format!("Fetching {id}..."));
+        self.awaiting_info = Some(awaiting);
     }
-    pub fn is_warning(&self) -> bool {
-        if let Some(i) = self.warning_timer {
-            if i.elapsed() < Duration::from_millis(250) {
-                return true;
+    fn finish(&mut self, awaiting: AwaitingInfo) {
+        match awaiting {
+            AwaitingInfo::Add {
+                id,
+                version,
+                fallback,
+                features,
+            } => {
+                let version = match version {
+                    Some(version) => Ok(version),
+                    None => self.latest_version(&id, fallback),
+                };
+                search::add_version(self, id, version, &features);
+            }
+            AwaitingInfo::Versions(id) => match self.info_cache.error(&id).map(str::to_owned) {
+                Some(e) => {
+                    self.error();
+                    self.command_line
+                        .error(format!("Unable to fetch the versions of {id}: {e}"));
+                }
+                None => self.version_picker.open(id),
+            },
+        }
+    }
+    /// The newest version that isn't yanked and works with the project's `rust-version`
+    fn latest_version(&self, id: &str, fallback: Option<String>) -> Result<String> {
+        match self.info_cache.get(id) {
+            Some(info) => info
+                .latest_compatible(self.rust_version.as_ref())
+                .map(|v| v.num.to_owned())
+                .ok_or_else(|| anyhow!("{id} has no versions that aren't yanked")),
+            None => fallback.ok_or_else(|| {
+                let e = self.info_cache.error(id).unwrap_or("no response");
+                anyhow!("Unable to fetch {id}: {e}")
+            }),
+        }
+    }
+    /// Fetches info in the background for the crates on screen whenever they
+    /// change, an open crate's details come first
+    fn prefetch_info(&mut self) {
+        if let Some(c) = &self.details_page.krate {
+            if !self.info_cache.contains(&c.id) {
+                self.worker.send(Request::Info(c.id.to_owned()));
+            }
+        }
+        let ids = match self.tab {
+            0 => self.search_page.visible_ids(),
+            // Versions are needed to tell what's yanked
+            1 => self.manage_page.dependency_ids(),
+            _ => favourites::visible_ids(self),
+        };
+        if ids != self.prefetched {
+            self.info_cache.prefetch(&mut self.worker, &ids);
+            self.prefetched = ids;
+        }
+    }
+    /// Warnings to show before adding a vulnerable version or one the license
+    /// policy warns about, errors if the policy blocks it
+    pub fn add_warnings(&self, id: &str, version: &str) -> Result<Vec<String>> {
+        let mut warnings = vec![];
+        if let Some(warning) = self.advisories.add_warning(id, version) {
+            warnings.push(warning);
+        }
+
+        if self.config.policy.is_enabled() {
+            // The policy can't be checked without the license so it's skipped when offline
+            if let Some(info) = self.info_cache.get(id) {
+                let license = info
+                    .version(version)
+                    .or(info.latest())
+                    .and_then(|v| v.license.as_deref());
+                if let Some(violation) = self.config.policy.violation(id, license) {
+                    match self.config.policy.action {
+                        PolicyAction::Block => return Err(anyhow!(violation)),
+                        PolicyAction::Warn => warnings.push(violation),
+                    }
+                }
             }
         }
-        false
+        Ok(warnings)
     }
-    pub fn primary_color(&self) -> Color {
+    /// Shows a manifest change so it can be checked before it's written
+    pub fn preview_edit(&mut self, edit: Result<ManifestEdit>, warnings: Vec<String>) -> bool {
+        match edit {
+            Ok(edit) if edit.is_empty() => {
+                self.warn();
+                self.command_line.info("Cargo.toml already has that".into());
+                false
+            }
+            Ok(edit) => {
+                self.edit_preview.open(edit, warnings);
+                true
+            }
+            Err(e) => {
+                self.warn();
+                self.command_line.error(e.to_string());
+                false
+            }
+        }
+    }
+    /// Writes a previewed change, keeping it so it can be undone
+    pub fn apply_edit(&mut self, edit: ManifestEdit) {
+        match edit.apply() {
+            Ok(_) => {
+                let undo = self.config.keys.get(Action::Undo).primary();
+                self.command_line
+                    .info(format!("{}, {undo} to undo", edit.description));
+                self.undo.push(edit);
+                self.redo.clear();
+            }
+            Err(e) => {
+                self.error();
+                self.command_line.error(e.to_string());
+            }
+        }
+    }
+    pub fn undo(&mut self) {
+        let Some(edit) = self.undo.pop() else {
+            self.warn();
+            return;
+        };
+        match edit.revert() {
+            Ok(_) => {
+                self.command_line
+                    .info(format!("Undid {}", edit.description.to_lowercase()));
+                self.redo.push(edit);
+            }
+            Err(e) => {
+                self.error();
+                self.command_line.error(e.to_string());
+            }
+        }
+    }
+    pub fn redo(&mut self) {
+        let Some(edit) = self.redo.pop() else {
+            self.warn();
+            return;
+        };
+        match edit.apply() {
+            Ok(_) => {
+                self.command_line
+                    .info(format!("Redid {}", edit.description.to_lowercase()));
+                self.undo.push(edit);
+            }
+            Err(e) => {
+                self.error();
+                self.command_line.error(e.to_string());
+            }
+        }
+    }
+    /// Hands finished background work to whatever asked for it and starts
+    /// anything the pages need next, returns whether anything changed
+    fn poll_background(&mut self) -> bool {
+        let responses = self.worker.poll();
+        let changed = !responses.is_empty();
+        for (request, response) in responses {
+            match request {
+                Request::Search { .. } => search::receive(self, request, response),
+                Request::Info(id) => {
+                    self.info_cache.receive(id.to_owned(), response);
+                    if self.awaiting_info.as_ref().is_some_and(|a| a.id() == id) {
+                        if let Some(awaiting) = self.awaiting_info.take() {
+                            self.command_line.clear_message();
+                            self.finish(awaiting);
+                        }
+                    }
+                }
+                Request::Compare(_) => compare::receive(self, request, response),
+                Request::Metadata | Request::Duplicates | Request::Unused => {
+                    manage::receive(self, request, response)
+                }
+                Request::LoadAdvisories(_) | Request::RefreshAdvisories(_) => match response {
+                    Ok(Response::Advisories(db)) => {
+                        self.advisories = db;
+                        if matches!(request, Request::RefreshAdvisories(_)) {
+                            self.command_line
+                                .info("Updated the advisory database".into());
+                        }
+                    }
+                    Err(e) => {
+                        self.error();
+                        self.command_line.error(e.to_string());
+                    }
+                    Ok(_) => {}
+                },
+            }
+        }
+        let searched = search::poll(self);
+        let reloaded = manage::poll(self);
+        changed || searched || reloaded
+    }
+    pub fn title_style(&self) -> Style {
         if self.is_error() {
-            rgb(self.config.colors.error)
+            self.theme.flash(self.theme.error)
         } else if self.is_warning() {
-            rgb(self.config.colors.warn)
+            self.theme.flash(self.theme.warning)
         } else {
-            rgb(self.config.colors.primary)
+            self.theme.title
         }
     }
 }
@@ -69,53 +402,127 @@ pub fn run_app<B: Backend>(
     app: &mut App,
     tick_rate: Duration,
 ) -> Result<()> {
+    // Only input, background work finishing and animations redraw
+    let mut redraw = true;
+    let mut animating = false;
     let mut last_tick = Instant::now();
     loop {
-        terminal.draw(|f| draw_ui(f, app))?;
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+        redraw |= app.poll_background();
+        if last_tick.elapsed() >= tick_rate {
+            last_tick = Instant::now();
+            // One more frame once an animation stops so its last frame is cleared
+            let was_animating = std::mem::replace(&mut animating, app.is_animating());
+            if animating || was_animating {
+                app.ticks = app.ticks.wrapping_add(1);
+                redraw = true;
+            }
+        }
+        if redraw {
+            terminal.draw(|f| draw_ui(f, app))?;
+            redraw = false;
+            app.prefetch_info();
+        }
 
+        let mut timeout = tick_rate.saturating_sub(last_tick.elapsed());
+        if let Some(debounce) = search::debounce_remaining(app) {
+            timeout = timeout.min(debounce);
+        }
         if crossterm::event::poll(timeout)? {
             let event = event::read()?;
+            // Moving the mouse only redraws when a page says the hovered row changed
+            let moved = matches!(
+                event,
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Moved,
+                    ..
+                })
+            );
+            redraw = !moved;
+
+            if let Event::Key(key) = event {
+                // Runs no matter what
+                if app.config.keys.get(Action::ForceQuit).matches(&key) {
+                    return Ok(());
+                }
+                app.command_line.clear_message();
+            }
+
+            // Modals take every event while open
+            if app.help_page.open {
+                help::event(app, event);
+                continue;
+            }
+            if app.command_line.active {
+                command_line::event(app, event);
+                continue;
+            }
+            if app.edit_preview.is_open() {
+                preview::event(app, event);
+                continue;
+            }
+            if app.version_picker.is_open() {
+                versions::event(app, event);
+                continue;
+            }
+            if app.compare_page.is_open() {
+                compare::event(app, event);
+                continue;
+            }
+            if app.details_page.is_open() {
+                details::event(app, event);
+                continue;
+            }
+
+            // Header tabs
+            if let Event::Mouse(e) = event {
+                if !app.config.mouse.enabled {
+                    continue;
+                }
+                if let MouseEventKind::Down(MouseButton::Left) = e.kind {
+                    let clicked = Rect::new(e.column, e.row, 1, 1);
+                    if let Some(tab) = app.tab_rects.iter().position(|r| r.intersects(clicked)) {
+                        app.tab = tab as u8;
+                        continue;
+                    }
+                }
+            }
 
             let can_use = match app.tab {
                 0 => search::event(app, event.clone()),
                 1 => manage::event(app, event.clone()),
-                2 => true,
+                2 => favourites::event(app, event.clone()),
                 _ => return Err(anyhow!("Tab index out of range")),
             };
+            if moved {
+                redraw = !can_use;
+            }
 
             if let Event::Key(key) = event {
-                // Runs no matter what
-                if let KeyCode::F(4) = key.code {
-                    return Ok(());
-                }
-
                 if !can_use {
                     continue;
                 }
 
                 // Only runs in current page didn't use it
-                match key.code {
+                match app.config.keys.find(&key, keymap::GLOBAL.actions) {
                     // Exit app
-                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    Some(Action::Quit) => return Ok(()),
 
                     // Switch Tabs
-                    KeyCode::Tab => app.tab = (app.tab + 1) % TAB_COUNT,
-                    KeyCode::BackTab => app.tab = (app.tab + TAB_COUNT - 1) % TAB_COUNT,
-                    KeyCode::Char('1') => app.tab = 0,
-                    KeyCode::Char('2') => app.tab = 1,
-                    KeyCode::Char('3') => app.tab = 2,
+                    Some(Action::NextTab) => app.tab = (app.tab + 1) % TAB_COUNT,
+                    Some(Action::PreviousTab) => app.tab = (app.tab + TAB_COUNT - 1) % TAB_COUNT,
+                    Some(Action::SearchTab) => app.tab = 0,
+                    Some(Action::ManageTab) => app.tab = 1,
+                    Some(Action::FavouritesTab) => app.tab = 2,
+
+                    Some(Action::Help) => app.help_page.open = true,
+                    Some(Action::CommandLine) => app.command_line.open(),
+                    Some(Action::Undo) => app.undo(),
+                    Some(Action::Redo) => app.redo(),
+                    Some(Action::Debug) => app.debug_panel = !app.debug_panel,
 
                     _ => {}
                 }
             }
         }
-
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
-        }
     }
 }
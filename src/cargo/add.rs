@@ -1,18 +1,277 @@
-use std::fs;
+use anyhow::{anyhow, Result};
+use toml_edit::{table, value, Array, Document, InlineTable, Item, Value};
 
-use anyhow::Result;
-use toml_edit::{value, Document};
+use super::ManifestEdit;
 
-use super::get_cargo_manifest_path;
+pub fn add(id: String, version: String, features: &[String]) -> Result<ManifestEdit> {
+    ManifestEdit::new(format!("Add {id} {version}"), |doc| {
+        add_to(doc, id, version, features)
+    })
+}
+
+/// Adds a dependency or updates the version of an existing one, keeping how
+/// it's written along with its other keys and comments
+pub(super) fn add_to(
+    doc: &mut Document,
+    id: String,
+    version: String,
+    features: &[String],
+) -> Result<()> {
+    let deps = doc
+        .entry("dependencies")
+        .or_insert(table())
+        .as_table_like_mut()
+        .ok_or_else(|| anyhow!("[dependencies] isn't a table"))?;
+
+    // Renamed dependencies are found by their package
+    let package = |item: &Item| {
+        item.get("package")
+            .and_then(|p| p.as_str())
+            .map(String::from)
+    };
+    let existing = deps
+        .iter()
+        .find(|(_, item)| package(item).as_ref() == Some(&id))
+        .map(|(name, _)| name.to_owned());
+    let name = existing.unwrap_or_else(|| id.to_owned());
 
-pub fn add(id: String, version: String) -> Result<()> {
-    let path = get_cargo_manifest_path()?;
-    let toml_text = fs::read_to_string(&path)?;
-    let mut doc = toml_text.parse::<Document>()?;
+    let Some(item) = deps.get_mut(&name) else {
+        deps.insert(&name, value(new_entry(version, features)));
+        return Ok(());
+    };
+    if package(item).is_some_and(|p| p != id) {
+        return Err(anyhow!(
+            "{name} is already used for the {} crate",
+            package(item).unwrap_or_default()
+        ));
+    }
 
-    doc["dependencies"][id] = value(version);
+    update(&name, item, version, features)
+}
+
+/// Sets the version of a dependency already in the manifest by the name it uses
+pub(super) fn set_version(doc: &mut Document, name: &str, version: String) -> Result<()> {
+    let item = doc
+        .get_mut("dependencies")
+        .and_then(|deps| deps.get_mut(name))
+        .ok_or_else(|| anyhow!("{name} is not in [dependencies]"))?;
+    update(name, item, version, &[])
+}
 
-    fs::write(path, doc.to_string())?;
+/// Updates an existing entry in whichever form it's written, adding any new features
+fn update(name: &str, item: &mut Item, version: String, features: &[String]) -> Result<()> {
+    // Cargo ignores a version next to `workspace = true`, so the change would do nothing
+    if item.get("workspace").and_then(Item::as_bool) == Some(true) {
+        return Err(anyhow!(
+            "{name} comes from the workspace, change it in the workspace's Cargo.toml"
+        ));
+    }
+    if !item.is_table_like() {
+        let v = item
+            .as_value_mut()
+            .ok_or_else(|| anyhow!("{name} isn't a valid dependency"))?;
+        replace(v, new_entry(version, features));
+        return Ok(());
+    }
 
+    match item.get_mut("version").and_then(Item::as_value_mut) {
+        Some(v) => replace(v, version.into()),
+        None => insert(item, "version", version.into()),
+    }
+    if !features.is_empty() {
+        match item.get_mut("features").and_then(Item::as_array_mut) {
+            Some(existing) => {
+                for feature in features {
+                    if !existing.iter().any(|f| f.as_str() == Some(feature)) {
+                        existing.push(feature);
+                    }
+                }
+            }
+            None => insert(item, "features", Array::from_iter(features).into()),
+        }
+    }
     Ok(())
 }
+
+/// Adds a key to a table, moving the space before an inline table's closing
+/// brace to after the new last value
+fn insert(item: &mut Item, key: &str, new: Value) {
+    match item.as_inline_table_mut() {
+        Some(table) => {
+            let suffix = table.iter_mut().last().and_then(|(_, last)| {
+                let suffix = last.decor().suffix().map(String::from);
+                last.decor_mut().set_suffix("");
+                suffix
+            });
+            table.insert(key, new);
+            if let (Some(suffix), Some((_, last))) = (suffix, table.iter_mut().last()) {
+                last.decor_mut().set_suffix(suffix);
+            }
+        }
+        None => {
+            if let Some(table) = item.as_table_like_mut() {
+                table.insert(key, value(new));
+            }
+        }
+    }
+}
+
+/// A plain version, or an inline table when there are features
+fn new_entry(version: String, features: &[String]) -> Value {
+    if features.is_empty() {
+        return version.into();
+    }
+    let mut table = InlineTable::new();
+    table.insert("version", version.into());
+    table.insert("features", Array::from_iter(features).into());
+    table.into()
+}
+
+/// Swaps a value keeping the whitespace and comments around it
+fn replace(old: &mut Value, new: Value) {
+    let decor = old.decor().clone();
+    *old = new;
+    *old.decor_mut() = decor;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(manifest: &str, id: &str, version: &str, features: &[&str]) -> Result<String> {
+        let mut doc = manifest.parse::<Document>()?;
+        let features: Vec<String> = features.iter().map(|f| f.to_string()).collect();
+        add_to(&mut doc, id.into(), version.into(), &features)?;
+        Ok(doc.to_string())
+    }
+
+    #[test]
+    fn adds_new_dependencies() {
+        let cases = [
+            ("", "serde", &[][..], "[dependencies]\nserde = \"1.0\"\n"),
+            (
+                "[dependencies]\nanyhow = \"1\"\n",
+                "serde",
+                &["derive"],
+                "[dependencies]\nanyhow = \"1\"\nserde = { version = \"1.0\", features = [\"derive\"] }\n",
+            ),
+        ];
+        for (manifest, id, features, expected) in cases {
+            assert_eq!(add(manifest, id, "1.0", features).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn updates_existing_dependencies_in_place() {
+        let cases = [
+            // A plain version keeps its comment
+            (
+                "[dependencies]\nserde = \"1.0\" # data\n",
+                &[][..],
+                "[dependencies]\nserde = \"1.2\" # data\n",
+            ),
+            // A plain version with new features becomes an inline table
+            (
+                "[dependencies]\nserde = \"1.0\"\n",
+                &["derive"],
+                "[dependencies]\nserde = { version = \"1.2\", features = [\"derive\"] }\n",
+            ),
+            // Inline tables keep their other keys and spacing
+            (
+                "[dependencies]\nserde = { version = \"1.0\", default-features = false }\n",
+                &[],
+                "[dependencies]\nserde = { version = \"1.2\", default-features = false }\n",
+            ),
+            (
+                "[dependencies]\nserde = { git = \"https://github.com/serde-rs/serde\" }\n",
+                &[],
+                "[dependencies]\nserde = { git = \"https://github.com/serde-rs/serde\", version = \"1.2\" }\n",
+            ),
+            // Features are merged without duplicates
+            (
+                "[dependencies]\nserde = { version = \"1.0\", features = [\"derive\"] }\n",
+                &["derive", "rc"],
+                "[dependencies]\nserde = { version = \"1.2\", features = [\"derive\", \"rc\"] }\n",
+            ),
+            // Dotted keys
+            (
+                "[dependencies]\nserde.version = \"1.0\"\nserde.features = [\"derive\"]\n",
+                &[],
+                "[dependencies]\nserde.version = \"1.2\"\nserde.features = [\"derive\"]\n",
+            ),
+            // A table of its own
+            (
+                "[dependencies.serde]\n# pinned for now\nversion = \"1.0\"\nfeatures = [\"derive\"]\n",
+                &[],
+                "[dependencies.serde]\n# pinned for now\nversion = \"1.2\"\nfeatures = [\"derive\"]\n",
+            ),
+            (
+                "[dependencies.serde]\nfeatures = [\"derive\"]\n",
+                &["rc"],
+                "[dependencies.serde]\nfeatures = [\"derive\", \"rc\"]\nversion = \"1.2\"\n",
+            ),
+            // Renamed dependencies are found by their package
+            (
+                "[dependencies]\nser = { package = \"serde\", version = \"1.0\" } # renamed\n",
+                &[],
+                "[dependencies]\nser = { package = \"serde\", version = \"1.2\" } # renamed\n",
+            ),
+        ];
+        for (manifest, features, expected) in cases {
+            assert_eq!(
+                add(manifest, "serde", "1.2", features).unwrap(),
+                expected,
+                "{manifest}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_rest_of_the_manifest() {
+        let manifest = "\
+# My project
+[package]
+name = \"app\" # the name
+
+[dependencies]
+# Serialization
+serde = \"1.0\"   # aligned
+tokio = { version = \"1\", features = [\"full\"] }
+
+[dev-dependencies]
+serde = \"0.9\"
+";
+        assert_eq!(
+            add(manifest, "serde", "1.2", &[]).unwrap(),
+            manifest.replace("serde = \"1.0\"", "serde = \"1.2\"")
+        );
+    }
+
+    #[test]
+    fn refuses_dependencies_it_cant_change() {
+        let cases = [
+            "[dependencies]\nserde = { workspace = true }\n",
+            "[dependencies]\nserde.workspace = true\n",
+            "[dependencies.serde]\nworkspace = true\nfeatures = [\"derive\"]\n",
+            // The name is taken by a different crate
+            "[dependencies]\nserde = { package = \"serde_json\", version = \"1\" }\n",
+            "dependencies = 1\n",
+        ];
+        for manifest in cases {
+            assert!(add(manifest, "serde", "1.2", &[]).is_err(), "{manifest}");
+        }
+    }
+
+    #[test]
+    fn set_version_uses_the_manifest_name() {
+        let mut doc = "[dependencies]\nser = { package = \"serde\", version = \"1.0\" }\n"
+            .parse::<Document>()
+            .unwrap();
+        set_version(&mut doc, "ser", "1.2".into()).unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "[dependencies]\nser = { package = \"serde\", version = \"1.2\" }\n"
+        );
+        assert!(set_version(&mut doc, "serde", "1.2".into()).is_err());
+    }
+}
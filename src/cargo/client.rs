@@ -0,0 +1,231 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+
+use super::http;
+
+/// crates.io asks crawlers for at most one request a second
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+const RETRIES: u32 = 3;
+/// Doubled after every failed attempt
+const BACKOFF: Duration = Duration::from_secs(1);
+/// Longer waits asked for by `Retry-After` fail instead of leaving the app hanging
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// The one client every request to crates.io goes through, shared by all threads
+struct Client {
+    agent: ureq::Agent,
+    /// The earliest the next request can start
+    next_slot: Mutex<Instant>,
+}
+
+/// Set up on first use, a bad proxy or certificate fails every request
+static CLIENT: OnceLock<Result<Client, String>> = OnceLock::new();
+
+static REQUESTS: AtomicU64 = AtomicU64::new(0);
+static RETRIED: AtomicU64 = AtomicU64::new(0);
+static RATE_LIMITED: AtomicU64 = AtomicU64::new(0);
+static FAILED: AtomicU64 = AtomicU64::new(0);
+static WAITED_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Counts of requests made to crates.io this session
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RequestStats {
+    pub requests: u64,
+    pub retried: u64,
+    /// Responses with a 429 status
+    pub rate_limited: u64,
+    /// Requests that still failed after retrying
+    pub failed: u64,
+    /// Total time spent waiting for the rate limit and between retries
+    pub waited: Duration,
+}
+
+pub fn request_stats() -> RequestStats {
+    RequestStats {
+        requests: REQUESTS.load(Ordering::Relaxed),
+        retried: RETRIED.load(Ordering::Relaxed),
+        rate_limited: RATE_LIMITED.load(Ordering::Relaxed),
+        failed: FAILED.load(Ordering::Relaxed),
+        waited: Duration::from_millis(WAITED_MS.load(Ordering::Relaxed)),
+    }
+}
+
+fn client() -> Result<&'static Client> {
+    CLIENT
+        .get_or_init(|| {
+            let agent = http::agent().map_err(|e| e.to_string())?;
+            Ok(Client {
+                agent,
+                next_slot: Mutex::new(Instant::now()),
+            })
+        })
+        .as_ref()
+        .map_err(|e| anyhow!("{e}"))
+}
+
+/// Gets a url from crates.io with a query string made from `query`, which is
+/// encoded, and parses the JSON response. Waits for the rate limit and retries
+/// server errors and dropped connections
+pub fn get_json<T: DeserializeOwned>(url: &str, query: &[(&str, &str)]) -> Result<T> {
+    let client = client()?;
+    let mut attempt = 0;
+    let mut delay = Duration::ZERO;
+    loop {
+        // Every thread waits out a delay the server asked for, not just this one
+        client.wait(delay);
+        REQUESTS.fetch_add(1, Ordering::Relaxed);
+        let request = query
+            .iter()
+            .fold(client.agent.get(url), |request, (param, value)| {
+                request.query(param, value)
+            });
+        let error = match request.call() {
+            Ok(res) => return Ok(res.into_json()?),
+            Err(e) => e,
+        };
+
+        let (status, retry_after) = match &error {
+            ureq::Error::Status(code, res) => (Some(*code), res.header("Retry-After")),
+            ureq::Error::Transport(_) => (None, None),
+        };
+        if status == Some(429) {
+            RATE_LIMITED.fetch_add(1, Ordering::Relaxed);
+        }
+        delay = match next_retry(status, retry_after, attempt) {
+            Retry::After(delay) => delay,
+            Retry::TooLong(delay) => {
+                FAILED.fetch_add(1, Ordering::Relaxed);
+                return Err(anyhow!(
+                    "crates.io asked to wait {}s before trying again",
+                    delay.as_secs()
+                ));
+            }
+            Retry::GiveUp => {
+                FAILED.fetch_add(1, Ordering::Relaxed);
+                return Err(error.into());
+            }
+        };
+        attempt += 1;
+        RETRIED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Client {
+    /// Sleeps until this thread's turn under the rate limit, at least `delay` from now
+    fn wait(&self, delay: Duration) {
+        let now = Instant::now();
+        let start = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+            let start = (*next_slot).max(now + delay);
+            *next_slot = start + MIN_INTERVAL;
+            start
+        };
+        let wait = start - now;
+        if !wait.is_zero() {
+            WAITED_MS.fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// What to do after an attempt fails
+#[derive(Debug, PartialEq)]
+enum Retry {
+    After(Duration),
+    /// crates.io asked to wait longer than `MAX_RETRY_AFTER`
+    TooLong(Duration),
+    GiveUp,
+}
+
+/// Whether to retry after an attempt failed with a status, or `None` when the
+/// connection dropped, and how long to wait first
+fn next_retry(status: Option<u16>, retry_after: Option<&str>, attempt: u32) -> Retry {
+    let asked = match status {
+        Some(429 | 503) => retry_after.and_then(parse_retry_after),
+        Some(code) if code >= 500 => None,
+        None => None,
+        Some(_) => return Retry::GiveUp,
+    };
+    let backoff = BACKOFF * 2u32.pow(attempt);
+    match asked {
+        Some(delay) if delay > MAX_RETRY_AFTER => Retry::TooLong(delay),
+        _ if attempt >= RETRIES => Retry::GiveUp,
+        delay => Retry::After(delay.unwrap_or(backoff).max(backoff)),
+    }
+}
+
+/// The delay in a `Retry-After` header, crates.io sends seconds rather than a date
+fn parse_retry_after(header: &str) -> Option<Duration> {
+    let secs = header.trim().parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_retry_after() {
+        let cases = [
+            ("0", Some(0)),
+            ("30", Some(30)),
+            (" 5 ", Some(5)),
+            ("", None),
+            ("-1", None),
+            ("1.5", None),
+            ("Wed, 21 Oct 2015 07:28:00 GMT", None),
+        ];
+        for (header, secs) in cases {
+            assert_eq!(
+                parse_retry_after(header),
+                secs.map(Duration::from_secs),
+                "{header:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn retries_with_backoff() {
+        let secs = |s| Retry::After(Duration::from_secs(s));
+        let too_long = |s| Retry::TooLong(Duration::from_secs(s));
+        let cases = [
+            // Dropped connections and server errors back off from a second
+            (None, None, 0, secs(1)),
+            (None, None, 1, secs(2)),
+            (None, None, 2, secs(4)),
+            (None, None, 3, Retry::GiveUp),
+            (Some(500), None, 0, secs(1)),
+            (Some(502), Some("10"), 1, secs(2)),
+            // Only rate limits and unavailability say how long to wait
+            (Some(429), None, 0, secs(1)),
+            (Some(429), Some("10"), 0, secs(10)),
+            (Some(503), Some("10"), 2, secs(10)),
+            (Some(429), Some("3"), 2, secs(4)),
+            (Some(429), Some("soon"), 1, secs(2)),
+            (Some(429), Some("10"), 3, Retry::GiveUp),
+            (Some(429), Some("60"), 0, secs(60)),
+            (Some(429), Some("61"), 0, too_long(61)),
+            (Some(503), Some("3600"), 3, too_long(3600)),
+            // Anything else won't change by asking again
+            (Some(404), None, 0, Retry::GiveUp),
+            (Some(403), Some("10"), 0, Retry::GiveUp),
+            (Some(304), None, 0, Retry::GiveUp),
+        ];
+        for (status, retry_after, attempt, expected) in cases {
+            assert_eq!(
+                next_retry(status, retry_after, attempt),
+                expected,
+                "{status:?} {retry_after:?} attempt {attempt}"
+            );
+        }
+    }
+}
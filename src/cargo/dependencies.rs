@@ -0,0 +1,85 @@
+use std::fs;
+
+use anyhow::Result;
+use semver::{Version, VersionReq};
+use toml_edit::{Document, Item};
+
+use super::{get_cargo_manifest_path, locked_packages};
+
+/// A dependency from the manifest with the version Cargo.lock resolved it to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectDependency {
+    /// The name used in the manifest
+    pub name: String,
+    /// The crate on crates.io, differs from `name` when renamed with `package`
+    pub package: String,
+    /// Missing for path and git dependencies without a version
+    pub req: Option<String>,
+    pub locked: Option<String>,
+}
+
+/// Names of the crates in the manifest's `[dependencies]` table
+pub fn dependencies() -> Result<Vec<String>> {
+    let path = get_cargo_manifest_path()?;
+    let toml_text = fs::read_to_string(path)?;
+    let doc = toml_text.parse::<Document>()?;
+
+    let names = doc
+        .get("dependencies")
+        .and_then(|deps| deps.as_table_like())
+        .map(|deps| deps.iter().map(|(name, _)| name.to_owned()).collect())
+        .unwrap_or_default();
+
+    Ok(names)
+}
+
+/// The manifest's `[dependencies]` with their requirements and locked versions
+pub fn project_dependencies() -> Result<Vec<ProjectDependency>> {
+    let path = get_cargo_manifest_path()?;
+    let toml_text = fs::read_to_string(path)?;
+    let doc = toml_text.parse::<Document>()?;
+    let locked = locked_packages().unwrap_or_default();
+
+    let Some(deps) = doc.get("dependencies").and_then(|d| d.as_table_like()) else {
+        return Ok(vec![]);
+    };
+
+    let dependencies = deps
+        .iter()
+        .map(|(name, item)| {
+            let field = |key: &str| {
+                item.as_table_like()
+                    .and_then(|t| t.get(key))
+                    .and_then(Item::as_str)
+                    .map(|s| s.to_owned())
+            };
+            let req = item
+                .as_str()
+                .map(|s| s.to_owned())
+                .or_else(|| field("version"));
+            let package = field("package").unwrap_or_else(|| name.to_owned());
+
+            // When several versions are locked pick the one the requirement matches
+            let candidates: Vec<_> = locked.iter().filter(|p| p.name == package).collect();
+            let parsed_req = req.as_deref().and_then(|r| VersionReq::parse(r).ok());
+            let locked = candidates
+                .iter()
+                .find(|p| {
+                    parsed_req.as_ref().is_some_and(|req| {
+                        Version::parse(&p.version).is_ok_and(|v| req.matches(&v))
+                    })
+                })
+                .or(candidates.first())
+                .map(|p| p.version.to_owned());
+
+            ProjectDependency {
+                name: name.to_owned(),
+                package,
+                req,
+                locked,
+            }
+        })
+        .collect();
+
+    Ok(dependencies)
+}
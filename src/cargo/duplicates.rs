@@ -0,0 +1,466 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use semver::{Version, VersionReq};
+
+use super::{
+    info, locked_packages, project_dependencies, rust_version, set_version, version_dependencies,
+    Dependency, InfoResponse, LockedPackage, ManifestEdit,
+};
+
+/// A crate locked at more than one version
+#[derive(Debug)]
+pub struct Duplicate {
+    pub name: String,
+    /// Oldest first
+    pub versions: Vec<DuplicateVersion>,
+    /// Direct dependencies to bump so only the newest version is left
+    pub bumps: Result<Vec<Bump>>,
+}
+
+#[derive(Debug)]
+pub struct DuplicateVersion {
+    pub version: String,
+    /// Packages depending on this version, as `name version`
+    pub dependents: Vec<String>,
+}
+
+/// Bumping a dependency in the manifest to a newer version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bump {
+    /// The name used in the manifest
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// Whether the bump is known to drop the old version without code changes,
+    /// rather than only changing something in between or crossing a major version
+    pub certain: bool,
+}
+
+/// Every crate in Cargo.lock at more than one version, with the bumps that would
+/// consolidate them, this fetches from crates.io so it's slow
+pub fn duplicates() -> Result<Vec<Duplicate>> {
+    let packages = locked_packages()?;
+    let graph = Graph::new(&packages);
+    let manifest = project_dependencies().unwrap_or_default();
+    let rust_version = rust_version().ok().flatten();
+    let mut infos: HashMap<String, InfoResponse> = HashMap::new();
+
+    let mut names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+    names.sort_unstable();
+    let mut duplicates = vec![];
+    for name in names.windows(2).filter(|w| w[0] == w[1]).map(|w| w[0]) {
+        if duplicates.iter().any(|d: &Duplicate| d.name == name) {
+            continue;
+        }
+        let mut versions: Vec<usize> = (0..packages.len())
+            .filter(|&i| packages[i].name == name)
+            .collect();
+        versions.sort_by_key(|&i| Version::parse(&packages[i].version).ok());
+        let Some((&newest, older)) = versions.split_last() else {
+            continue;
+        };
+
+        let mut bumps = Ok(vec![]);
+        for &old in older {
+            for path in graph.paths_from_direct(old) {
+                // Only `[dependencies]` can be bumped through the manifest
+                let direct = &packages[path[0]];
+                let Some(dep) = manifest.iter().find(|d| d.package == direct.name) else {
+                    continue;
+                };
+                let bump = match (path.len(), rust_version.as_ref()) {
+                    // The project depends on the old version itself, the registry
+                    // is only needed to check the newest version's `rust-version`
+                    (1, Some(_)) => cached_info(&mut infos, name).map(|info| {
+                        let release = info.version(&packages[newest].version);
+                        bump_direct(
+                            &dep.name,
+                            direct,
+                            &packages[newest],
+                            release,
+                            rust_version.as_ref(),
+                        )
+                    }),
+                    (1, None) => Ok(bump_direct(
+                        &dep.name,
+                        direct,
+                        &packages[newest],
+                        None,
+                        None,
+                    )),
+                    (_, _) => suggest(
+                        &mut infos,
+                        rust_version.as_ref(),
+                        &dep.name,
+                        direct,
+                        &packages[path[1]],
+                        &packages[newest],
+                    ),
+                };
+                match (&mut bumps, bump) {
+                    (Ok(bumps), Ok(Some(bump))) if !bumps.contains(&bump) => bumps.push(bump),
+                    (Ok(_), Err(e)) => bumps = Err(e),
+                    _ => {}
+                }
+            }
+        }
+
+        duplicates.push(Duplicate {
+            name: name.to_owned(),
+            versions: versions
+                .iter()
+                .map(|&i| DuplicateVersion {
+                    version: packages[i].version.to_owned(),
+                    dependents: graph.dependents[i]
+                        .iter()
+                        .map(|&d| format!("{} {}", packages[d].name, packages[d].version))
+                        .collect(),
+                })
+                .collect(),
+            bumps,
+        });
+    }
+    Ok(duplicates)
+}
+
+/// Applies bumps to the manifest through the same path as `add`
+pub fn bump(bumps: &[Bump]) -> Result<ManifestEdit> {
+    let description = bumps
+        .iter()
+        .map(|b| format!("{} to {}", b.name, b.to))
+        .collect::<Vec<_>>()
+        .join(", ");
+    ManifestEdit::new(format!("Bump {description}"), |doc| {
+        for bump in bumps {
+            set_version(doc, &bump.name, bump.to.to_owned())?;
+        }
+        Ok(())
+    })
+}
+
+/// Checks whether the newest release of a direct dependency moves off the
+/// package it currently pulls the old version in through
+fn suggest(
+    infos: &mut HashMap<String, InfoResponse>,
+    rust_version: Option<&Version>,
+    name: &str,
+    direct: &LockedPackage,
+    next: &LockedPackage,
+    newest: &LockedPackage,
+) -> Result<Option<Bump>> {
+    let Some(latest) = cached_info(infos, &direct.name)?.latest_compatible(rust_version) else {
+        return Ok(None);
+    };
+    let parse = |v: &str| Version::parse(v).ok();
+    if parse(&latest.num) <= parse(&direct.version) {
+        return Ok(None);
+    }
+
+    let deps = version_dependencies(&direct.name, &latest.num)?;
+    Ok(bump_to(name, direct, next, newest, &latest.num, &deps))
+}
+
+/// Fetches a crate's info the first time it's needed
+fn cached_info<'a>(
+    infos: &'a mut HashMap<String, InfoResponse>,
+    name: &str,
+) -> Result<&'a InfoResponse> {
+    if !infos.contains_key(name) {
+        infos.insert(name.to_owned(), info(name)?);
+    }
+    Ok(&infos[name])
+}
+
+/// The bump for a dependency the project uses the old version of itself, unless
+/// the newest version's `rust-version` is newer than the project's. Only a bump
+/// within the same semver compatible range is certain to build without changes
+fn bump_direct(
+    name: &str,
+    direct: &LockedPackage,
+    newest: &LockedPackage,
+    release: Option<&super::info::Version>,
+    rust_version: Option<&Version>,
+) -> Option<Bump> {
+    if release.is_some_and(|r| !r.is_compatible(rust_version)) {
+        return None;
+    }
+    let certain = match (
+        Version::parse(&direct.version),
+        Version::parse(&newest.version),
+    ) {
+        (Ok(old), Ok(new)) => is_semver_compatible(&old, &new),
+        _ => false,
+    };
+    Some(Bump {
+        name: name.to_owned(),
+        from: direct.version.to_owned(),
+        to: newest.version.to_owned(),
+        certain,
+    })
+}
+
+/// Whether cargo would treat two versions as compatible, which is when their
+/// leftmost non-zero parts match
+fn is_semver_compatible(a: &Version, b: &Version) -> bool {
+    match (a.major, a.minor) {
+        (0, 0) => b.major == 0 && b.minor == 0 && a.patch == b.patch,
+        (0, minor) => b.major == 0 && b.minor == minor,
+        (major, _) => b.major == major,
+    }
+}
+
+/// The bump to `latest` of a direct dependency, given what that release depends
+/// on, if it moves off the package it pulls the old version in through
+fn bump_to(
+    name: &str,
+    direct: &LockedPackage,
+    next: &LockedPackage,
+    newest: &LockedPackage,
+    latest: &str,
+    deps: &[Dependency],
+) -> Option<Bump> {
+    let parse = |v: &str| Version::parse(v).ok();
+    let req = deps
+        .iter()
+        .filter(|d| d.kind != "dev")
+        .find(|d| d.crate_id == next.name)
+        .map(|d| VersionReq::parse(&d.req));
+    let matches = |req: &VersionReq, version: &str| parse(version).is_some_and(|v| req.matches(&v));
+    let certain = match req {
+        // It no longer depends on it at all
+        None => true,
+        // There's no telling what it depends on
+        Some(Err(_)) => false,
+        // It moved to the newest duplicated version
+        Some(Ok(req)) if next.name == newest.name && matches(&req, &newest.version) => true,
+        // Something in between changes, which may drop the old version
+        Some(Ok(req)) if next.name != newest.name && !matches(&req, &next.version) => false,
+        Some(Ok(_)) => return None,
+    };
+
+    Some(Bump {
+        name: name.to_owned(),
+        from: direct.version.to_owned(),
+        to: latest.to_owned(),
+        certain,
+    })
+}
+
+/// Cargo.lock as a graph of package indices
+struct Graph {
+    dependents: Vec<Vec<usize>>,
+    /// Packages the project's own packages depend on
+    direct: HashSet<usize>,
+}
+
+impl Graph {
+    fn new(packages: &[LockedPackage]) -> Self {
+        // Entries are `name`, or `name version` and maybe `(source)` when ambiguous
+        let find = |entry: &str| {
+            let mut parts = entry.split(' ');
+            let name = parts.next()?;
+            let version = parts.next();
+            packages
+                .iter()
+                .position(|p| p.name == name && (version.is_none() || version == Some(&p.version)))
+        };
+        let mut graph = Graph {
+            dependents: vec![vec![]; packages.len()],
+            direct: HashSet::new(),
+        };
+        for (i, package) in packages.iter().enumerate() {
+            for dep in package.dependencies.iter().filter_map(|d| find(d)) {
+                graph.dependents[dep].push(i);
+                if package.source.is_none() && packages[dep].source.is_some() {
+                    graph.direct.insert(dep);
+                }
+            }
+        }
+        graph
+    }
+
+    /// The shortest path from each direct dependency down to a package
+    fn paths_from_direct(&self, target: usize) -> Vec<Vec<usize>> {
+        let mut paths = vec![];
+        let mut seen = HashSet::from([target]);
+        let mut queue = VecDeque::from([vec![target]]);
+        while let Some(path) = queue.pop_front() {
+            let first = path[0];
+            if self.direct.contains(&first) {
+                paths.push(path.to_owned());
+            }
+            for &dependent in &self.dependents[first] {
+                if seen.insert(dependent) {
+                    let mut path = path.to_owned();
+                    path.insert(0, dependent);
+                    queue.push_back(path);
+                }
+            }
+        }
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    fn package(name: &str, version: &str, dependencies: &[&str]) -> LockedPackage {
+        LockedPackage {
+            name: name.into(),
+            version: version.into(),
+            source: (name != "app").then(|| REGISTRY.into()),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    fn dependency(crate_id: &str, req: &str, kind: &str) -> Dependency {
+        Dependency {
+            crate_id: crate_id.into(),
+            req: req.into(),
+            kind: kind.into(),
+            optional: false,
+        }
+    }
+
+    /// `app` uses syn 2 directly and syn 1 through `derive`
+    fn packages() -> Vec<LockedPackage> {
+        vec![
+            package("app", "0.1.0", &["derive", "syn 2.0.10"]),
+            package("derive", "0.3.0", &["syn 1.0.109", "quote"]),
+            package("quote", "1.0.0", &[]),
+            package("syn", "1.0.109", &["quote"]),
+            package("syn", "2.0.10", &["quote"]),
+        ]
+    }
+
+    #[test]
+    fn graph_links_dependents() {
+        let graph = Graph::new(&packages());
+        let expected: [&[usize]; 5] = [&[], &[0], &[1, 3, 4], &[1], &[0]];
+        assert_eq!(graph.dependents, expected);
+        assert_eq!(graph.direct, HashSet::from([1, 4]));
+    }
+
+    #[test]
+    fn graph_skips_unknown_entries() {
+        let packages = [package("app", "0.1.0", &["missing", "quote 9.9.9"])];
+        let graph = Graph::new(&packages);
+        assert_eq!(graph.dependents, [Vec::<usize>::new()]);
+        assert!(graph.direct.is_empty());
+    }
+
+    #[test]
+    fn paths_from_direct() {
+        let graph = Graph::new(&packages());
+        assert_eq!(graph.paths_from_direct(3), [vec![1, 3]]);
+        assert_eq!(graph.paths_from_direct(4), [vec![4]]);
+        assert_eq!(graph.paths_from_direct(2), [vec![1, 2], vec![4, 2]]);
+        assert!(graph.paths_from_direct(0).is_empty());
+    }
+
+    #[test]
+    fn bump_to() {
+        let packages = packages();
+        let (derive, syn1, syn2, quote) = (&packages[1], &packages[3], &packages[4], &packages[2]);
+        let bump = |certain| {
+            Some(Bump {
+                name: "derive".into(),
+                from: "0.3.0".into(),
+                to: "0.4.0".into(),
+                certain,
+            })
+        };
+        let cases = [
+            // Dropped syn altogether
+            (syn1, vec![dependency("quote", "1", "normal")], bump(true)),
+            // Only a dev dependency on the old version is left
+            (syn1, vec![dependency("syn", "1", "dev")], bump(true)),
+            // Moved to the newest syn
+            (syn1, vec![dependency("syn", "2.0", "normal")], bump(true)),
+            // Still on the old syn
+            (syn1, vec![dependency("syn", "1.0", "normal")], None),
+            // Moved to a syn that isn't the newest either
+            (syn1, vec![dependency("syn", "=1.0.50", "normal")], None),
+            // Something in between changed, which may or may not drop syn 1
+            (quote, vec![dependency("quote", "2", "normal")], bump(false)),
+            (quote, vec![dependency("quote", "1", "normal")], None),
+            // Requirements that don't parse could be on either version
+            (
+                syn1,
+                vec![dependency("syn", "not a req", "normal")],
+                bump(false),
+            ),
+        ];
+        for (next, deps, expected) in cases {
+            assert_eq!(
+                super::bump_to("derive", derive, next, syn2, "0.4.0", &deps),
+                expected,
+                "{next:?} {deps:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn bump_direct() {
+        let release = |rust_version: Option<&str>| super::super::info::Version {
+            num: "2.0.10".into(),
+            yanked: false,
+            license: None,
+            rust_version: rust_version.map(Into::into),
+            created_at: String::new(),
+            features: Default::default(),
+        };
+        let msrv = Version::new(1, 60, 0);
+        let cases = [
+            // syn 1 to 2 may need code changes
+            ("syn", "1.0.109", "2.0.10", None, None, Some(false)),
+            ("syn", "2.0.1", "2.0.10", None, None, Some(true)),
+            ("tokio", "0.2.1", "0.2.25", None, None, Some(true)),
+            ("tokio", "0.2.25", "0.3.0", None, None, Some(false)),
+            ("x", "0.0.1", "0.0.2", None, None, Some(false)),
+            ("x", "not a version", "1.0.0", None, None, Some(false)),
+            // The newest version needs a newer Rust than the project
+            (
+                "syn",
+                "2.0.1",
+                "2.0.10",
+                Some(release(Some("1.61"))),
+                Some(&msrv),
+                None,
+            ),
+            (
+                "syn",
+                "2.0.1",
+                "2.0.10",
+                Some(release(Some("1.56"))),
+                Some(&msrv),
+                Some(true),
+            ),
+            (
+                "syn",
+                "2.0.1",
+                "2.0.10",
+                Some(release(None)),
+                Some(&msrv),
+                Some(true),
+            ),
+            (
+                "syn",
+                "2.0.1",
+                "2.0.10",
+                Some(release(Some("1.61"))),
+                None,
+                Some(true),
+            ),
+        ];
+        for (name, from, to, release, rust_version, certain) in cases {
+            let (old, new) = (package(name, from, &[]), package(name, to, &[]));
+            let bump = super::bump_direct(name, &old, &new, release.as_ref(), rust_version);
+            assert_eq!(bump.map(|b| b.certain), certain, "{from} to {to}");
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use toml_edit::Document;
+
+use super::get_cargo_manifest_path;
+use crate::config::data_dir;
+
+/// Lines of unchanged context around each change in a diff
+const CONTEXT: usize = 3;
+
+/// A change to Cargo.toml that hasn't been written yet
+#[derive(Debug, Clone)]
+pub struct ManifestEdit {
+    /// What the change does, like `Add serde 1.0.152`
+    pub description: String,
+    path: PathBuf,
+    before: String,
+    after: String,
+}
+
+impl ManifestEdit {
+    /// Reads the manifest and makes a change to it in memory
+    pub fn new(
+        description: String,
+        change: impl FnOnce(&mut Document) -> Result<()>,
+    ) -> Result<Self> {
+        let path = get_cargo_manifest_path()?;
+        let before = fs::read_to_string(&path)?;
+        let mut doc = before.parse::<Document>()?;
+        change(&mut doc)?;
+        Ok(Self {
+            description,
+            path,
+            before,
+            after: doc.to_string(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before == self.after
+    }
+
+    /// The change as a unified diff
+    pub fn diff(&self) -> Vec<String> {
+        diff(&self.before, &self.after)
+    }
+
+    /// Backs up the manifest and writes the change, as long as nothing else
+    /// changed the manifest since it was read
+    pub fn apply(&self) -> Result<PathBuf> {
+        self.write(&self.before, &self.after)
+    }
+
+    /// Puts the manifest back how it was before the change
+    pub fn revert(&self) -> Result<PathBuf> {
+        self.write(&self.after, &self.before)
+    }
+
+    fn write(&self, expected: &str, text: &str) -> Result<PathBuf> {
+        let current = fs::read_to_string(&self.path)?;
+        if current != expected {
+            return Err(anyhow!("Cargo.toml was changed by something else"));
+        }
+        let backup = backup_path()?;
+        fs::write(&backup, current)?;
+        fs::write(&self.path, text)?;
+        Ok(backup)
+    }
+}
+
+/// A timestamped file in the data directory's `backups` folder
+fn backup_path() -> Result<PathBuf> {
+    let dir = data_dir()?.join("backups");
+    fs::create_dir_all(&dir)?;
+    let project = get_cargo_manifest_path()?
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "project".into());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(dir.join(format!("{project}-Cargo-{}.toml", timestamp(now))))
+}
+
+/// A UTC time since the epoch like `20230415-093012.045`
+fn timestamp(now: Duration) -> String {
+    let secs = now.as_secs();
+    let (days, time) = ((secs / 86400) as i64, secs % 86400);
+
+    // Days since the epoch to a civil date, from Howard Hinnant's algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year}{month:02}{day:02}-{:02}{:02}{:02}.{:03}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        now.subsec_millis()
+    )
+}
+
+/// A unified diff of two texts by line
+pub fn diff(before: &str, after: &str) -> Vec<String> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = match a[i] == b[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    // Every line tagged with ' ', '-' or '+' and its line numbers in both texts
+    let mut ops = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i], i, j));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a[i], i, j));
+            i += 1;
+        } else {
+            ops.push(('+', b[j], i, j));
+            j += 1;
+        }
+    }
+
+    let mut lines = vec!["--- a/Cargo.toml".to_owned(), "+++ b/Cargo.toml".to_owned()];
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    let mut k = 0;
+    while k < changes.len() {
+        // Grow the hunk while the next change is close enough to share context
+        let start = changes[k].saturating_sub(CONTEXT);
+        let mut end = changes[k];
+        while k + 1 < changes.len() && changes[k + 1] <= end + 2 * CONTEXT + 1 {
+            k += 1;
+            end = changes[k];
+        }
+        let end = (end + CONTEXT + 1).min(ops.len());
+        k += 1;
+
+        let hunk = &ops[start..end];
+        let count = |tag: char| hunk.iter().filter(|op| op.0 == ' ' || op.0 == tag).count();
+        // An empty side starts at the line before it, which is 0 for an empty file
+        let start = |line: usize, count: usize| if count == 0 { line } else { line + 1 };
+        let (old_count, new_count) = (count('-'), count('+'));
+        lines.push(format!(
+            "@@ -{},{old_count} +{},{new_count} @@",
+            start(hunk[0].2, old_count),
+            start(hunk[0].3, new_count),
+        ));
+        lines.extend(hunk.iter().map(|(tag, line, _, _)| format!("{tag}{line}")));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(lines: std::ops::RangeInclusive<usize>) -> String {
+        lines.map(|n| format!("{n}\n")).collect()
+    }
+
+    /// The hunks of a diff without the file headers
+    fn hunks(before: &str, after: &str) -> Vec<String> {
+        diff(before, after).split_off(2)
+    }
+
+    #[test]
+    fn diff_headers() {
+        assert_eq!(
+            diff("a\n", "b\n")[..2],
+            ["--- a/Cargo.toml", "+++ b/Cargo.toml"]
+        );
+        assert!(hunks("a\nb\n", "a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn diff_insert() {
+        let after = numbered(1..=10).replace("5\n", "5\nx\n");
+        assert_eq!(
+            hunks(&numbered(1..=10), &after),
+            ["@@ -3,6 +3,7 @@", " 3", " 4", " 5", "+x", " 6", " 7", " 8"]
+        );
+    }
+
+    #[test]
+    fn diff_delete() {
+        assert_eq!(
+            hunks(&numbered(1..=10), &numbered(2..=10)),
+            ["@@ -1,4 +1,3 @@", "-1", " 2", " 3", " 4"]
+        );
+    }
+
+    #[test]
+    fn diff_change() {
+        let after = numbered(1..=10).replace("7\n", "seven\n");
+        assert_eq!(
+            hunks(&numbered(1..=10), &after),
+            [
+                "@@ -4,7 +4,7 @@",
+                " 4",
+                " 5",
+                " 6",
+                "-7",
+                "+seven",
+                " 8",
+                " 9",
+                " 10"
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_end_of_file() {
+        assert_eq!(
+            hunks(&numbered(1..=5), &numbered(1..=6)),
+            ["@@ -3,3 +3,4 @@", " 3", " 4", " 5", "+6"]
+        );
+        assert_eq!(
+            hunks(&numbered(1..=6), &numbered(1..=5)),
+            ["@@ -3,4 +3,3 @@", " 3", " 4", " 5", "-6"]
+        );
+    }
+
+    #[test]
+    fn diff_empty_file() {
+        assert_eq!(hunks("", "a\nb\n"), ["@@ -0,0 +1,2 @@", "+a", "+b"]);
+        assert_eq!(hunks("a\nb\n", ""), ["@@ -1,2 +0,0 @@", "-a", "-b"]);
+        assert!(hunks("", "").is_empty());
+    }
+
+    #[test]
+    fn diff_merges_nearby_changes() {
+        let changed = |lines: [usize; 2]| -> String {
+            (1..=20)
+                .map(|n| match lines.contains(&n) {
+                    true => "x\n".to_owned(),
+                    false => format!("{n}\n"),
+                })
+                .collect()
+        };
+        let headers = |after: &str| -> Vec<String> {
+            hunks(&numbered(1..=20), after)
+                .into_iter()
+                .filter(|l| l.starts_with("@@"))
+                .collect()
+        };
+        // Six unchanged lines between changes are all shared context
+        assert_eq!(headers(&changed([2, 9])), ["@@ -1,12 +1,12 @@"]);
+        // Seven aren't, so they're separate hunks
+        assert_eq!(
+            headers(&changed([2, 10])),
+            ["@@ -1,5 +1,5 @@", "@@ -7,7 +7,7 @@"]
+        );
+    }
+
+    #[test]
+    fn timestamps() {
+        let at = |secs: u64, millis: u64| timestamp(Duration::from_millis(secs * 1000 + millis));
+        assert_eq!(at(0, 0), "19700101-000000.000");
+        assert_eq!(at(86399, 999), "19700101-235959.999");
+        assert_eq!(at(951782400, 0), "20000229-000000.000");
+        assert_eq!(at(1709210096, 789), "20240229-123456.789");
+        assert_eq!(at(1677628800, 0), "20230301-000000.000");
+        assert_eq!(at(1704067199, 0), "20231231-235959.000");
+        // 2100 isn't a leap year
+        assert_eq!(at(4107456000, 0), "21000228-000000.000");
+        assert_eq!(at(4107542400, 0), "21000301-000000.000");
+    }
+}
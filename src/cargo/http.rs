@@ -0,0 +1,203 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use directories::BaseDirs;
+
+use super::USER_AGENT;
+
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+compile_error!("Enable the rustls or native-tls feature so cratui can reach crates.io");
+
+const HOST: &str = "crates.io";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The `[http]` settings cargo would use, from its config files and environment
+#[derive(Debug, Default)]
+pub struct HttpConfig {
+    pub proxy: Option<String>,
+    /// A PEM bundle of extra certificate authorities to trust
+    pub cainfo: Option<PathBuf>,
+}
+
+impl HttpConfig {
+    /// Reads `http.proxy` and `http.cainfo` the way cargo does, the closest
+    /// `.cargo/config.toml` wins and `CARGO_HTTP_*` variables override them all
+    pub fn load() -> Self {
+        let mut config = Self::default();
+        let cargo_home = env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| BaseDirs::new().map(|dirs| dirs.home_dir().join(".cargo")));
+        let dirs = env::current_dir()
+            .map(|cwd| {
+                cwd.ancestors()
+                    .map(|dir| dir.join(".cargo"))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for dir in dirs.into_iter().chain(cargo_home) {
+            let Some((path, http)) = read_http_table(&dir) else {
+                continue;
+            };
+            if config.proxy.is_none() {
+                config.proxy = http.get("proxy").and_then(|p| p.as_str()).map(String::from);
+            }
+            if config.cainfo.is_none() {
+                // Relative paths are relative to the directory holding `.cargo`
+                config.cainfo = http.get("cainfo").and_then(|p| p.as_str()).map(|p| {
+                    let base = path
+                        .parent()
+                        .and_then(Path::parent)
+                        .unwrap_or(Path::new(""));
+                    base.join(p)
+                });
+            }
+        }
+        if let Ok(proxy) = env::var("CARGO_HTTP_PROXY") {
+            config.proxy = Some(proxy);
+        }
+        if let Ok(cainfo) = env::var("CARGO_HTTP_CAINFO") {
+            config.cainfo = Some(cainfo.into());
+        }
+        config.proxy = config.proxy.filter(|p| !p.is_empty());
+        config
+    }
+
+    /// The proxy for crates.io, cargo's own setting first then `HTTPS_PROXY`,
+    /// unless `NO_PROXY` leaves crates.io out
+    pub fn proxy_for_crates_io(&self) -> Option<String> {
+        let var = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+        };
+        if var(&["NO_PROXY", "no_proxy"]).is_some_and(|no_proxy| matches_no_proxy(&no_proxy, HOST))
+        {
+            return None;
+        }
+        self.proxy
+            .to_owned()
+            .or_else(|| var(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]))
+    }
+}
+
+/// The `[http]` table of the config file in a `.cargo` directory, cargo reads
+/// `config` as well as `config.toml`
+fn read_http_table(dir: &Path) -> Option<(PathBuf, toml::value::Table)> {
+    ["config.toml", "config"].into_iter().find_map(|name| {
+        let path = dir.join(name);
+        let config: toml::Value = fs::read_to_string(&path).ok()?.parse().ok()?;
+        let http = config.get("http")?.as_table()?.to_owned();
+        Some((path, http))
+    })
+}
+
+/// Whether a `NO_PROXY` list of hosts and domains covers a host, host names
+/// aren't case sensitive
+fn matches_no_proxy(no_proxy: &str, host: &str) -> bool {
+    let (no_proxy, host) = (no_proxy.to_ascii_lowercase(), host.to_ascii_lowercase());
+    no_proxy.split(',').map(str::trim).any(|entry| {
+        let entry = entry.split(':').next().unwrap_or_default();
+        let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+        entry == "*"
+            || (!domain.is_empty() && (host == domain || host.ends_with(&format!(".{domain}"))))
+    })
+}
+
+/// An agent for crates.io using the proxy and certificate authorities cargo would
+pub(super) fn agent() -> Result<ureq::Agent> {
+    let config = HttpConfig::load();
+    let mut builder = ureq::AgentBuilder::new()
+        .user_agent(USER_AGENT)
+        .timeout(TIMEOUT);
+    if let Some(proxy) = config.proxy_for_crates_io() {
+        let parsed =
+            ureq::Proxy::new(&proxy).map_err(|e| anyhow!("Unable to use proxy {proxy}: {e}"))?;
+        builder = builder.proxy(parsed);
+    }
+    let builder = tls(builder, config.cainfo.as_deref())
+        .map_err(|e| anyhow!("Unable to set up TLS for crates.io: {e}"))?;
+    Ok(builder.build())
+}
+
+#[cfg(feature = "rustls")]
+fn tls(builder: ureq::AgentBuilder, cainfo: Option<&Path>) -> Result<ureq::AgentBuilder> {
+    use rustls_pki_types::{pem::PemObject, CertificateDer};
+    use std::sync::Arc;
+
+    let Some(cainfo) = cainfo else {
+        return Ok(builder);
+    };
+    let mut roots =
+        rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    for cert in CertificateDer::pem_file_iter(cainfo)
+        .map_err(|e| anyhow!("Unable to read {}: {e}", cainfo.display()))?
+    {
+        roots.add(cert.map_err(|e| anyhow!("Bad certificate in {}: {e}", cainfo.display()))?)?;
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(builder.tls_config(Arc::new(config)))
+}
+
+#[cfg(all(feature = "native-tls", not(feature = "rustls")))]
+fn tls(builder: ureq::AgentBuilder, cainfo: Option<&Path>) -> Result<ureq::AgentBuilder> {
+    use std::sync::Arc;
+
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let mut connector = native_tls::TlsConnector::builder();
+    if let Some(cainfo) = cainfo {
+        let pem = fs::read_to_string(cainfo)
+            .map_err(|e| anyhow!("Unable to read {}: {e}", cainfo.display()))?;
+        // Certificates are added one at a time, so split the bundle
+        for cert in pem.split_inclusive(END).filter(|c| c.contains(END)) {
+            connector.add_root_certificate(native_tls::Certificate::from_pem(cert.as_bytes())?);
+        }
+    }
+    Ok(builder.tls_connector(Arc::new(connector.build()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_proxy_matching() {
+        let cases = [
+            ("crates.io", true),
+            ("static.crates.io,crates.io", true),
+            ("  example.com , crates.io ", true),
+            (".crates.io", true),
+            ("*.crates.io", true),
+            ("io", true),
+            ("*", true),
+            ("crates.io:443", true),
+            ("CRATES.IO", true),
+            // Not crates.io
+            ("", false),
+            (",,", false),
+            (".", false),
+            ("*.", false),
+            ("static.crates.io", false),
+            ("rates.io", false),
+            ("notcrates.io", false),
+            ("crates.io.example.com", false),
+            ("example.com:8080", false),
+        ];
+        for (no_proxy, expected) in cases {
+            assert_eq!(
+                matches_no_proxy(no_proxy, "crates.io"),
+                expected,
+                "{no_proxy:?}"
+            );
+        }
+        assert!(matches_no_proxy("crates.io", "static.crates.io"));
+    }
+}
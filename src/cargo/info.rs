@@ -0,0 +1,128 @@
+use super::{
+    lowest_version, registry, rust_version,
+    search::{Crate, Meta},
+};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub fn info(id: &str) -> Result<InfoResponse> {
+    registry().info(id)
+}
+
+/// The version `add` uses when none is given, one that works with the
+/// project's `rust-version` if there is one
+pub fn latest_version(id: &str) -> Result<String> {
+    let rust_version = rust_version().ok().flatten();
+    info(id)?
+        .latest_compatible(rust_version.as_ref())
+        .map(|v| v.num.to_owned())
+        .ok_or_else(|| anyhow!("{id} has no versions that aren't yanked"))
+}
+
+/// The dependencies a published version declares
+pub fn version_dependencies(id: &str, version: &str) -> Result<Vec<Dependency>> {
+    registry().version_dependencies(id, version)
+}
+
+/// How many crates depend on this one
+pub fn reverse_dependency_count(id: &str) -> Result<usize> {
+    registry().reverse_dependency_count(id)
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InfoResponse {
+    #[serde(rename = "crate")]
+    pub krate: Crate,
+    /// Newest first
+    #[serde(default)]
+    pub versions: Vec<Version>,
+}
+
+impl InfoResponse {
+    /// The newest version that isn't yanked, preferring stable releases
+    pub fn latest(&self) -> Option<&Version> {
+        self.newest(true, |_| true)
+            .or_else(|| self.newest(false, |_| true))
+    }
+    /// Like `latest` but skipping versions that need a newer Rust than the
+    /// project's `rust-version`, falls back to `latest` if none are compatible
+    pub fn latest_compatible(&self, rust_version: Option<&semver::Version>) -> Option<&Version> {
+        let compatible = |v: &Version| v.is_compatible(rust_version);
+        let latest = self.latest()?;
+        // Pre-releases are only considered when there's no stable release at all
+        let stable = semver::Version::parse(&latest.num).is_ok_and(|v| v.pre.is_empty());
+        self.newest(stable, compatible).or(Some(latest))
+    }
+    fn newest(&self, stable: bool, filter: impl Fn(&Version) -> bool) -> Option<&Version> {
+        self.versions
+            .iter()
+            .filter(|v| !v.yanked && filter(v))
+            .filter_map(|v| Some((semver::Version::parse(&v.num).ok()?, v)))
+            .filter(|(num, _)| !stable || num.pre.is_empty())
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, v)| v)
+    }
+    pub fn version(&self, num: &str) -> Option<&Version> {
+        self.versions.iter().find(|v| v.num == num)
+    }
+    pub fn is_yanked(&self, num: &str) -> bool {
+        self.version(num).is_some_and(|v| v.yanked)
+    }
+    /// Whether a requirement names a yanked version or only matches yanked ones
+    pub fn is_req_yanked(&self, req: &str) -> bool {
+        // Only a full version like `1.2.3` names a specific release
+        let named = lowest_version(req)
+            .filter(|_| req.matches('.').count() >= 2)
+            .is_some_and(|v| self.is_yanked(&v.to_string()));
+        let Ok(req) = semver::VersionReq::parse(req) else {
+            return named;
+        };
+        named
+            || !self.versions.iter().any(|v| {
+                !v.yanked && semver::Version::parse(&v.num).is_ok_and(|num| req.matches(&num))
+            })
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    pub num: String,
+    pub yanked: bool,
+    pub license: Option<String>,
+    pub rust_version: Option<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+impl Version {
+    /// Whether the version's MSRV is at most the project's, versions without
+    /// one and projects without one are always compatible
+    pub fn is_compatible(&self, rust_version: Option<&semver::Version>) -> bool {
+        let msrv = self.rust_version.as_deref().and_then(lowest_version);
+        match (msrv, rust_version) {
+            (Some(msrv), Some(rust_version)) => msrv <= *rust_version,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub crate_id: String,
+    pub req: String,
+    /// normal, dev or build
+    pub kind: String,
+    pub optional: bool,
+}
+
+#[derive(Deserialize)]
+pub(super) struct DependenciesResponse {
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct ReverseDependenciesResponse {
+    pub meta: Meta,
+}
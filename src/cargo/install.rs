@@ -1,10 +1,13 @@
 use anyhow::Result;
-use std::process::{Child, Command};
+use std::{process::Command, thread};
 
-pub fn install(id: String) -> Result<Child> {
-    Ok(Command::new("cargo")
+pub fn install(id: String) -> Result<()> {
+    let mut child = Command::new("cargo")
         .arg("install")
         .arg(id)
         .arg("-q")
-        .spawn()?)
+        .spawn()?;
+    // Reap the process once it finishes so it doesn't linger as a zombie
+    thread::spawn(move || child.wait());
+    Ok(())
 }
@@ -0,0 +1,34 @@
+use std::fs;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::get_cargo_manifest_path;
+
+/// A package resolved in the project's Cargo.lock
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Missing for path dependencies and the project itself
+    pub source: Option<String>,
+    /// Names of the packages it depends on, followed by a version when it's ambiguous
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+/// Every package in the Cargo.lock next to the manifest, empty if there isn't one
+pub fn locked_packages() -> Result<Vec<LockedPackage>> {
+    let path = get_cargo_manifest_path()?.with_file_name("Cargo.lock");
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let lockfile: Lockfile = toml::from_str(&fs::read_to_string(path)?)?;
+    Ok(lockfile.package)
+}
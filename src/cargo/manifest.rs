@@ -0,0 +1,32 @@
+use std::fs;
+
+use anyhow::Result;
+use semver::Version;
+use toml_edit::Document;
+
+use super::{get_cargo_manifest_path, lowest_version};
+
+/// The project's `package.rust-version`, following `workspace = true` to the
+/// workspace table when it's in the same manifest
+pub fn rust_version() -> Result<Option<Version>> {
+    let path = get_cargo_manifest_path()?;
+    let toml_text = fs::read_to_string(path)?;
+    let doc = toml_text.parse::<Document>()?;
+
+    let package = doc.get("package").and_then(|p| p.get("rust-version"));
+    let inherited = package
+        .and_then(|v| v.get("workspace"))
+        .and_then(|w| w.as_bool())
+        .unwrap_or(false);
+    let rust_version = if inherited {
+        doc.get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("rust-version"))
+    } else {
+        package
+    };
+
+    Ok(rust_version
+        .and_then(|v| v.as_str())
+        .and_then(lowest_version))
+}
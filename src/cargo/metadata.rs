@@ -0,0 +1,112 @@
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::get_cargo_manifest_path;
+
+/// The parts of `cargo metadata`'s output cratui uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct Metadata {
+    pub packages: Vec<Package>,
+    #[serde(default)]
+    pub workspace_members: Vec<String>,
+    /// The resolved dependency graph, `None` with `--no-deps`
+    pub resolve: Option<Resolve>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Package {
+    /// Opaque id used to refer to the package in the dependency graph
+    #[serde(default, skip_serializing)]
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    /// Missing for the project's own packages
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resolve {
+    pub nodes: Vec<Node>,
+    /// Missing for virtual workspaces, use the workspace members instead
+    pub root: Option<String>,
+}
+
+/// A package in the dependency graph with its resolved features
+#[derive(Debug, Clone, Deserialize)]
+pub struct Node {
+    pub id: String,
+    #[serde(default)]
+    pub deps: Vec<NodeDep>,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeDep {
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<DepKind>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepKind {
+    /// `dev` or `build`, `None` for normal dependencies
+    pub kind: Option<String>,
+}
+
+impl NodeDep {
+    /// `dev` or `build` when it's only ever that kind of dependency
+    pub fn kind(&self) -> Option<&str> {
+        let mut kinds = self.dep_kinds.iter().map(|k| k.kind.as_deref());
+        let first = kinds.next().flatten()?;
+        kinds.all(|k| k == Some(first)).then_some(first)
+    }
+}
+
+/// Runs `cargo metadata` on the project, which also resolves dependencies
+/// that aren't in Cargo.lock yet
+pub fn metadata() -> Result<Metadata> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(get_cargo_manifest_path()?)
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "cargo metadata failed: {}",
+            stderr.lines().next().unwrap_or_default()
+        ));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+impl Metadata {
+    /// Every package the project depends on, not counting its own
+    pub fn dependencies(&self) -> impl Iterator<Item = &Package> {
+        self.packages.iter().filter(|p| p.source.is_some())
+    }
+
+    pub fn package(&self, id: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.id == id)
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.resolve.as_ref()?.nodes.iter().find(|n| n.id == id)
+    }
+
+    /// Where the dependency graph starts, the root package or every workspace member
+    pub fn roots(&self) -> Vec<&str> {
+        match self.resolve.as_ref().and_then(|r| r.root.as_deref()) {
+            Some(root) => vec![root],
+            None => self.workspace_members.iter().map(|m| m.as_str()).collect(),
+        }
+    }
+
+    /// Whether more than one version of the package is in the graph
+    pub fn is_duplicated(&self, name: &str) -> bool {
+        self.packages.iter().filter(|p| p.name == name).count() > 1
+    }
+}
@@ -1,9 +1,21 @@
 use anyhow::{anyhow, Result};
+use semver::Version;
 use std::{env, path::PathBuf};
 
+pub mod client;
+pub use client::*;
+
+pub mod http;
+
+pub mod registry;
+pub use registry::*;
+
 pub mod search;
 pub use search::*;
 
+pub mod edit;
+pub use edit::*;
+
 pub mod add;
 pub use add::*;
 
@@ -13,14 +25,52 @@ pub use remove::*;
 pub mod install;
 pub use install::*;
 
+pub mod info;
+pub use info::*;
+
+pub mod dependencies;
+pub use dependencies::*;
+
+pub mod lockfile;
+pub use lockfile::*;
+
+pub mod metadata;
+pub use metadata::*;
+
+pub mod manifest;
+pub use manifest::*;
+
+pub mod duplicates;
+pub use duplicates::*;
+
+pub mod unused;
+pub use unused::*;
+
 const API: &str = "https://crates.io/api/v1/crates";
 
+// User Agent header, required by crates.io api
+const USER_AGENT: &str = "cratui (https://github.com/LiamGallagher737/cratui)";
+
 fn get_cargo_manifest_path() -> Result<PathBuf> {
     let mut dir = env::current_dir()?;
-    dir.push("cargo.toml");
+    dir.push("Cargo.toml");
     match dir.try_exists() {
         Ok(true) => Ok(dir),
         Ok(false) => Err(anyhow!("Cannot find Cargo.toml")),
         Err(e) => Err(anyhow!("Unable to check if Cargo.toml exsists {e}")),
     }
 }
+
+/// The oldest version a requirement allows, `^1.2` is treated as `1.2.0`
+pub fn lowest_version(req: &str) -> Option<Version> {
+    let req = req.split(',').next()?.trim();
+    let req = req.trim_start_matches(|c: char| "^~=>< ".contains(c));
+    if let Ok(version) = Version::parse(req) {
+        return Some(version);
+    }
+    let mut parts = req.split('.').map(|p| p.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some(Version::new(major, minor, patch))
+}
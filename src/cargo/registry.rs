@@ -0,0 +1,286 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+
+use super::{
+    get_json, Crate, DependenciesResponse, Dependency, InfoResponse, Meta,
+    ReverseDependenciesResponse, SearchResponse, Sort, API,
+};
+
+/// Where crate information comes from
+pub trait RegistryClient: Send + Sync {
+    /// A page of search results, `page` starts at 0
+    fn search(&self, query: &str, page: usize, limit: usize, sort: Sort) -> Result<SearchResponse>;
+    fn info(&self, id: &str) -> Result<InfoResponse>;
+    /// The dependencies a published version declares
+    fn version_dependencies(&self, id: &str, version: &str) -> Result<Vec<Dependency>>;
+    /// How many crates depend on this one
+    fn reverse_dependency_count(&self, id: &str) -> Result<usize>;
+}
+
+static REGISTRY: OnceLock<Box<dyn RegistryClient>> = OnceLock::new();
+
+/// The registry every lookup uses, crates.io unless something else was picked at startup
+pub fn registry() -> &'static dyn RegistryClient {
+    REGISTRY.get_or_init(|| Box::new(CratesIo)).as_ref()
+}
+
+/// Picks the registry, which can only happen before the first lookup
+pub fn use_registry(client: impl RegistryClient + 'static) -> Result<()> {
+    REGISTRY
+        .set(Box::new(client))
+        .map_err(|_| anyhow!("The registry is already in use"))
+}
+
+/// The live crates.io API
+pub struct CratesIo;
+
+impl RegistryClient for CratesIo {
+    fn search(&self, query: &str, page: usize, limit: usize, sort: Sort) -> Result<SearchResponse> {
+        let limit = limit.min(100); // Crates.io only allows 100 per page
+        let (page, limit) = ((page + 1).to_string(), limit.to_string());
+        get_json(
+            API,
+            &[
+                ("q", query),
+                ("page", &page),
+                ("per_page", &limit),
+                ("sort", sort.as_str()),
+            ],
+        )
+    }
+
+    fn info(&self, id: &str) -> Result<InfoResponse> {
+        get_json(&format!("{API}/{}", crate_name(id)?), &[])
+    }
+
+    fn version_dependencies(&self, id: &str, version: &str) -> Result<Vec<Dependency>> {
+        let (id, version) = (crate_name(id)?, crate_version(version)?);
+        let res: DependenciesResponse =
+            get_json(&format!("{API}/{id}/{version}/dependencies"), &[])?;
+        Ok(res.dependencies)
+    }
+
+    fn reverse_dependency_count(&self, id: &str) -> Result<usize> {
+        // Only the total is needed so ask for as little as possible
+        let url = format!("{API}/{}/reverse_dependencies", crate_name(id)?);
+        let res: ReverseDependenciesResponse = get_json(&url, &[("per_page", "1")])?;
+        Ok(res.meta.total)
+    }
+}
+
+/// Responses recorded from crates.io, laid out like the API's paths under
+/// `/api/v1/crates`:
+///
+/// - `serde.json` for a crate's info
+/// - `serde/1.0.152/dependencies.json` for a version's dependencies
+/// - `serde/reverse_dependencies.json` for its dependents
+/// - `search/<query>.json` for search results, with the query percent encoded
+///   like `search/serde%20json.json`. Without one the crates whose info is
+///   recorded are searched instead
+pub struct Fixtures {
+    dir: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new(dir: PathBuf) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(anyhow!("{} is not a directory", dir.display()));
+        }
+        Ok(Self { dir })
+    }
+
+    fn read<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T> {
+        let path = self.dir.join(path);
+        let text = fs::read_to_string(&path)
+            .map_err(|_| anyhow!("No recorded response at {}", path.display()))?;
+        serde_json::from_str(&text).map_err(|e| anyhow!("Unable to parse {}: {e}", path.display()))
+    }
+
+    /// Every crate with recorded info that mentions each word of the query
+    fn matching(&self, query: &str, sort: Sort) -> Result<Vec<Crate>> {
+        let mut infos = vec![];
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Ok(info) = self.read::<InfoResponse>(&path) {
+                    infos.push(info);
+                }
+            }
+        }
+
+        let query = query.to_lowercase();
+        infos.retain(|info| {
+            let c = &info.krate;
+            let text = format!("{} {}", c.id, c.description.as_deref().unwrap_or_default());
+            let text = text.to_lowercase();
+            query.split_whitespace().all(|word| text.contains(word))
+        });
+
+        // Versions are newest first
+        let released = |info: &InfoResponse, newest: bool| {
+            let version = match newest {
+                true => info.versions.first(),
+                false => info.versions.last(),
+            };
+            version.map(|v| v.created_at.to_owned()).unwrap_or_default()
+        };
+        infos.sort_by_key(|info| info.krate.id.to_owned());
+        match sort {
+            Sort::Relevance => {
+                infos.sort_by_key(|info| (info.krate.id != query, Reverse(info.krate.downloads)))
+            }
+            Sort::Downloads => infos.sort_by_key(|info| Reverse(info.krate.downloads)),
+            Sort::RecentDownloads => infos.sort_by_key(|info| Reverse(info.krate.recent_downloads)),
+            Sort::RecentUpdates => infos.sort_by_key(|info| Reverse(released(info, true))),
+            Sort::New => infos.sort_by_key(|info| Reverse(released(info, false))),
+            Sort::Alpha => {}
+        }
+        Ok(infos.into_iter().map(|info| info.krate).collect())
+    }
+}
+
+impl RegistryClient for Fixtures {
+    fn search(&self, query: &str, page: usize, limit: usize, sort: Sort) -> Result<SearchResponse> {
+        let recorded = Path::new("search").join(format!("{}.json", encode(query)));
+        let crates = match self.dir.join(&recorded).exists() {
+            true => self.read::<SearchResponse>(recorded)?.crates,
+            false => self.matching(query, sort)?,
+        };
+
+        let limit = limit.clamp(1, 100);
+        let total = crates.len();
+        let more = (page + 1) * limit < total;
+        Ok(SearchResponse {
+            crates: crates.into_iter().skip(page * limit).take(limit).collect(),
+            meta: Meta {
+                next_page: more.then(|| format!("?q={}&page={}", encode(query), page + 2)),
+                prev_page: (page > 0).then(|| format!("?q={}&page={page}", encode(query))),
+                total,
+            },
+        })
+    }
+
+    fn info(&self, id: &str) -> Result<InfoResponse> {
+        self.read(format!("{}.json", crate_name(id)?))
+    }
+
+    fn version_dependencies(&self, id: &str, version: &str) -> Result<Vec<Dependency>> {
+        let (id, version) = (crate_name(id)?, crate_version(version)?);
+        let res: DependenciesResponse =
+            self.read(Path::new(id).join(version).join("dependencies.json"))?;
+        Ok(res.dependencies)
+    }
+
+    fn reverse_dependency_count(&self, id: &str) -> Result<usize> {
+        let res: ReverseDependenciesResponse =
+            self.read(Path::new(crate_name(id)?).join("reverse_dependencies.json"))?;
+        Ok(res.meta.total)
+    }
+}
+
+/// Checks an id is a crate name before it goes into a url or path, they're
+/// only letters, digits, `-` and `_`
+fn crate_name(id: &str) -> Result<&str> {
+    match !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        true => Ok(id),
+        false => Err(anyhow!("{id:?} isn't a valid crate name")),
+    }
+}
+
+/// Checks a version is semver before it goes into a url or path
+fn crate_version(version: &str) -> Result<&str> {
+    semver::Version::parse(version)
+        .map(|_| version)
+        .map_err(|e| anyhow!("{version:?} isn't a valid version: {e}"))
+}
+
+/// Percent encodes everything but letters, digits, `-` and `_`, so the result
+/// is safe in a query string and as a file name that can't leave its directory
+fn encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => char::from(b).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_queries() {
+        let cases = [
+            ("serde", "serde"),
+            ("serde_json tokio-util", "serde_json%20tokio-util"),
+            ("a&b=c#d?", "a%26b%3Dc%23d%3F"),
+            ("../../etc/passwd", "%2E%2E%2F%2E%2E%2Fetc%2Fpasswd"),
+            ("..", "%2E%2E"),
+            ("C:\\x", "C%3A%5Cx"),
+            ("100%", "100%25"),
+            ("café", "caf%C3%A9"),
+            ("", ""),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(encode(query), expected, "{query:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_ids_and_versions_that_arent_crates() {
+        let dir = std::env::temp_dir().join(format!("cratui-ids-{}", std::process::id()));
+        fs::create_dir_all(dir.join("fixtures")).unwrap();
+        // Would parse if it were read, so only the check can fail
+        fs::write(dir.join("x.json"), "{}").unwrap();
+        let fixtures = Fixtures::new(dir.join("fixtures")).unwrap();
+
+        for id in ["../x", "", "a/b", "a?b", "a#b", "a b", "..", "%2E"] {
+            let e = fixtures.info(id).unwrap_err().to_string();
+            assert!(e.contains("isn't a valid crate name"), "{id:?}: {e}");
+            assert!(fixtures.reverse_dependency_count(id).is_err(), "{id:?}");
+        }
+        for version in ["../../x", "1.0", "1.0.0/../..", "1.0.0?x"] {
+            let e = fixtures.version_dependencies("serde", version);
+            assert!(
+                e.unwrap_err().to_string().contains("isn't a valid version"),
+                "{version:?}"
+            );
+        }
+        // Valid ones get as far as looking for the recording
+        let e = fixtures.info("serde_json-2").unwrap_err().to_string();
+        assert!(e.starts_with("No recorded response"), "{e}");
+        let e = fixtures.version_dependencies("serde", "1.0.0-rc.1+build.5");
+        assert!(e
+            .unwrap_err()
+            .to_string()
+            .starts_with("No recorded response"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recorded_searches_stay_in_the_search_directory() {
+        let dir = std::env::temp_dir().join(format!("cratui-fixtures-{}", std::process::id()));
+        fs::create_dir_all(dir.join("search")).unwrap();
+        // Neither parses, so reading one is an error
+        fs::write(dir.join("secret.json"), "not json").unwrap();
+        fs::write(dir.join("search").join("a%2Fb.json"), "not json").unwrap();
+        let fixtures = Fixtures::new(dir.clone()).unwrap();
+        let search = |query| fixtures.search(query, 0, 10, Sort::Relevance);
+
+        assert!(search("a/b").is_err());
+        assert!(search("../secret").is_ok_and(|res| res.crates.is_empty()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
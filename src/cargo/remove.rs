@@ -1,22 +1,16 @@
-use std::fs;
+use anyhow::{anyhow, Result};
+use toml_edit::Item;
 
-use anyhow::Result;
-use toml_edit::{Document, Item};
+use super::ManifestEdit;
 
-use super::get_cargo_manifest_path;
-
-pub fn remove(id: String) -> Result<()> {
-    let path = get_cargo_manifest_path()?;
-    let toml_text = fs::read_to_string(&path)?;
-    let mut doc = toml_text.parse::<Document>()?;
-
-    if let Item::Table(deps) = &mut doc["dependencies"] {
-        if deps.contains_key(&id) {
-            deps.remove(&id);
+pub fn remove(id: String) -> Result<ManifestEdit> {
+    ManifestEdit::new(format!("Remove {id}"), |doc| {
+        match &mut doc["dependencies"] {
+            Item::Table(deps) if deps.contains_key(&id) => {
+                deps.remove(&id);
+                Ok(())
+            }
+            _ => Err(anyhow!("{id} is not in [dependencies]")),
         }
-    }
-
-    fs::write(path, doc.to_string())?;
-
-    Ok(())
+    })
 }
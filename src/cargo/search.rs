@@ -1,21 +1,10 @@
-use super::API;
-use anyhow::Result;
+use super::registry;
+use anyhow::{anyhow, Error, Result};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
-// User Agent header, required by crates.io api
-const UA_HEADER_KEY: &str = "User-Agent";
-const UA_HEADER_VALUE: &str = "cratui (https://github.com/LiamGallagher737/cratui)";
-
-pub fn search(query: &str, page: usize, limit: usize) -> Result<SearchResponse> {
-    let limit = limit.min(100); // Crates.io only allows 100 per page
-    let url = format!("{API}?q={query}&page={}&per_page={limit}", page + 1);
-
-    let res = ureq::get(&url)
-        .set(UA_HEADER_KEY, UA_HEADER_VALUE)
-        .call()?
-        .into_json()?;
-
-    Ok(res)
+pub fn search(query: &str, page: usize, limit: usize, sort: Sort) -> Result<SearchResponse> {
+    registry().search(query, page, limit, sort)
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -43,6 +32,15 @@ pub struct Crate {
     // pub updated_at: String,
 }
 
+impl Crate {
+    pub fn crates_io_url(&self) -> String {
+        format!("https://crates.io/crates/{}", self.id)
+    }
+    pub fn docs_url(&self) -> String {
+        format!("https://docs.rs/{}/latest", self.id)
+    }
+}
+
 // #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 // pub struct Links {
 //     pub owner_team: String,
@@ -59,3 +57,49 @@ pub struct Meta {
     pub prev_page: Option<String>,
     pub total: usize,
 }
+
+/// The orders crates.io can sort search results in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sort {
+    #[default]
+    Relevance,
+    Downloads,
+    RecentDownloads,
+    RecentUpdates,
+    New,
+    Alpha,
+}
+
+impl Sort {
+    pub const ALL: [Sort; 6] = [
+        Sort::Relevance,
+        Sort::Downloads,
+        Sort::RecentDownloads,
+        Sort::RecentUpdates,
+        Sort::New,
+        Sort::Alpha,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sort::Relevance => "relevance",
+            Sort::Downloads => "downloads",
+            Sort::RecentDownloads => "recent-downloads",
+            Sort::RecentUpdates => "recent-updates",
+            Sort::New => "new",
+            Sort::Alpha => "alpha",
+        }
+    }
+}
+
+impl FromStr for Sort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Sort::ALL
+            .into_iter()
+            .find(|sort| sort.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown sort '{s}'"))
+    }
+}
@@ -0,0 +1,138 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+
+use super::{get_cargo_manifest_path, project_dependencies};
+
+const SOURCE_DIRS: [&str; 4] = ["src", "tests", "benches", "examples"];
+
+/// Manifest names of `[dependencies]` the project's code never refers to.
+/// Crates only needed for their features or for linking show up here too
+pub fn unused_dependencies() -> Result<Vec<String>> {
+    let manifest = get_cargo_manifest_path()?;
+    let root = manifest.parent().unwrap_or(Path::new("."));
+
+    let mut sources = vec![];
+    for dir in SOURCE_DIRS {
+        read_sources(&root.join(dir), &mut sources);
+    }
+    read_sources(&root.join("build.rs"), &mut sources);
+
+    Ok(project_dependencies()?
+        .into_iter()
+        .map(|dep| dep.name)
+        // Code refers to renamed crates by the manifest name
+        .filter(|name| {
+            let ident = name.replace('-', "_");
+            !sources.iter().any(|source| references(source, &ident))
+        })
+        .collect())
+}
+
+/// Reads every `.rs` file at or under a path
+fn read_sources(path: &Path, sources: &mut Vec<String>) {
+    if path.is_dir() {
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            read_sources(&entry.path(), sources);
+        }
+    } else if path.extension().is_some_and(|e| e == "rs") {
+        if let Ok(source) = fs::read_to_string(path) {
+            sources.push(source);
+        }
+    }
+}
+
+/// Whether the source uses a crate in a path like `ident::thing`, or with
+/// `use ident`, `use {ident, other}` or `extern crate ident`
+fn references(source: &str, ident: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    source.match_indices(ident).any(|(i, _)| {
+        let before = &source[..i];
+        let after = &source[i + ident.len()..];
+        if before.ends_with(is_ident) || after.starts_with(is_ident) {
+            return false;
+        }
+        // Methods and items inside other paths like `crate::ident` aren't crates
+        let before = before.trim_end();
+        if before.ends_with('.')
+            || before
+                .strip_suffix("::")
+                .is_some_and(|b| b.ends_with(is_ident))
+        {
+            return false;
+        }
+        // Inside braces only a crate level `use { .. }` names crates
+        if before.ends_with(['{', ',']) {
+            if let Some(crate_level) = use_group(before) {
+                return crate_level;
+            }
+        }
+        let before = before.trim_end_matches("::").trim_end();
+        after.trim_start().starts_with("::")
+            || before.ends_with("use")
+            || before.ends_with("extern crate")
+    })
+}
+
+/// Whether the end of `before` is inside the braces of a crate level `use { .. }`,
+/// or `false` in a group under a path like `use std::{ .. }`, `None` outside of either
+fn use_group(before: &str) -> Option<bool> {
+    let mut depth = 0;
+    for (i, c) in before.char_indices().rev() {
+        match c {
+            '}' => depth += 1,
+            '{' if depth > 0 => depth -= 1,
+            '{' => {
+                let outer = before[..i].trim_end();
+                return match outer.strip_suffix("::") {
+                    Some(path) => Some(path.trim_end().ends_with("use")),
+                    None => outer.ends_with("use").then_some(true),
+                };
+            }
+            ';' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_references() {
+        let cases = [
+            ("use serde;", true),
+            ("use serde::Deserialize;", true),
+            ("pub use serde as s;", true),
+            ("use ::serde;", true),
+            ("use ::serde::de;", true),
+            ("use {serde, tokio};", true),
+            ("use {tokio, serde};", true),
+            ("use {tokio::sync, serde::{de, ser}};", true),
+            ("use ::{serde};", true),
+            ("use {\n    anyhow,\n    serde,\n};", true),
+            ("extern crate serde;", true),
+            ("#[derive(serde::Serialize)]", true),
+            ("let x = ::serde::de::value::Error;", true),
+            ("fn f() { serde::json() }", true),
+            ("fn f() { let a = (1, serde::json()); }", true),
+            // Not the crate
+            ("use my_serde;", false),
+            ("use serde_json;", false),
+            ("use crate::serde;", false),
+            ("use self::serde::Thing;", false),
+            ("use std::{io, serde};", false),
+            ("use tokio::{sync, serde::Thing};", false),
+            ("x.serde();", false),
+            ("let serde = 1;", false),
+            ("fn f() { let a = (serde, 1); }", false),
+            ("// serde is great", false),
+            ("", false),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(references(source, "serde"), expected, "{source}");
+        }
+    }
+}
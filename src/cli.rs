@@ -0,0 +1,288 @@
+use crate::{
+    advisories::AdvisoryDb,
+    cargo::{self, ManifestEdit, Sort},
+    config::{load_config, save_config, PolicyAction},
+};
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Crates.io as a Terminal User Interface, run without a command to open the TUI
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+    /// Use crates.io responses recorded in a directory instead of the network
+    #[arg(long, global = true, value_name = "DIR")]
+    pub registry_fixtures: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Search crates.io
+    Search {
+        query: String,
+        /// Page of results to show, starting at 1
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        /// Results per page, at most 100
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// relevance, downloads, recent-downloads, recent-updates, new or alpha
+        #[arg(long)]
+        sort: Option<Sort>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show information about a crate
+    Info {
+        id: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add a crate to the project's Cargo.toml
+    Add {
+        /// The crate, optionally with a version like serde@1
+        spec: String,
+        /// Features to enable
+        #[arg(short = 'F', long, value_delimiter = ',')]
+        features: Vec<String>,
+        /// Print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove a crate from the project's Cargo.toml
+    Remove {
+        id: String,
+        /// Print the change as a diff instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the licenses of every package the project depends on, fails if
+    /// any aren't allowed by the license policy
+    Licenses {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Manage favourite crates
+    Fav {
+        #[command(subcommand)]
+        command: FavCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum FavCommand {
+    /// List favourite crates
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add a crate to the favourites
+    Add { id: String },
+    /// Remove a crate from the favourites
+    Remove { id: String },
+}
+
+pub fn run(command: CliCommand) -> Result<()> {
+    match command {
+        CliCommand::Search {
+            query,
+            page,
+            limit,
+            sort,
+            json,
+        } => {
+            let sort = match sort {
+                Some(sort) => sort,
+                None => load_config()?.search.sort,
+            };
+            let res = cargo::search(&query, page.saturating_sub(1), limit, sort)?;
+            if json {
+                return print_json(&res);
+            }
+            let rows = res
+                .crates
+                .iter()
+                .map(|c| {
+                    vec![
+                        c.id.to_owned(),
+                        c.max_stable_version
+                            .to_owned()
+                            .unwrap_or_else(|| c.max_version.to_owned()),
+                        c.downloads.to_string(),
+                        c.description.to_owned().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            print_table(&["NAME", "VERSION", "DOWNLOADS", "DESCRIPTION"], rows);
+            println!("\n{} crates found", res.meta.total);
+        }
+        CliCommand::Info { id, json } => {
+            let res = cargo::info(&id)?;
+            if json {
+                return print_json(&res);
+            }
+            let c = res.krate;
+            let none = || "-".to_owned();
+            print_table(
+                &[],
+                vec![
+                    vec!["name".into(), c.id],
+                    vec![
+                        "version".into(),
+                        c.max_stable_version.unwrap_or(c.max_version),
+                    ],
+                    vec!["description".into(), c.description.unwrap_or_else(none)],
+                    vec!["downloads".into(), c.downloads.to_string()],
+                    vec!["recent downloads".into(), c.recent_downloads.to_string()],
+                    vec!["repository".into(), c.repository.unwrap_or_else(none)],
+                ],
+            );
+        }
+        CliCommand::Add {
+            spec,
+            features,
+            dry_run,
+        } => {
+            let (id, version) = match spec.split_once('@') {
+                Some((id, version)) => (id.to_owned(), version.to_owned()),
+                None => (spec.to_owned(), cargo::latest_version(&spec)?),
+            };
+            let config = load_config()?;
+            let advisories = AdvisoryDb::load(&config.advisories).unwrap_or_else(|e| {
+                eprintln!("warning: {e}");
+                AdvisoryDb::default()
+            });
+            if let Some(warning) = advisories.add_warning(&id, &version) {
+                eprintln!("warning: {warning}");
+            }
+            if config.policy.is_enabled() {
+                let info = cargo::info(&id)?;
+                let license = info
+                    .version(&version)
+                    .or(info.latest())
+                    .and_then(|v| v.license.as_deref());
+                if let Some(violation) = config.policy.violation(&id, license) {
+                    match config.policy.action {
+                        PolicyAction::Block => return Err(anyhow!(violation)),
+                        PolicyAction::Warn => eprintln!("warning: {violation}"),
+                    }
+                }
+            }
+            let edit = cargo::add(id.to_owned(), version.to_owned(), &features)?;
+            write_edit(edit, dry_run)?;
+        }
+        CliCommand::Remove { id, dry_run } => write_edit(cargo::remove(id)?, dry_run)?,
+        CliCommand::Licenses { json } => {
+            let policy = load_config()?.policy;
+            for error in policy.errors() {
+                eprintln!("warning: {error}");
+            }
+            let metadata = cargo::metadata()?;
+            let packages: Vec<_> = metadata.dependencies().collect();
+            if json {
+                return print_json(&packages);
+            }
+            let mut disallowed = 0;
+            let rows = packages
+                .iter()
+                .map(|p| {
+                    let allowed = policy.allows(p.license.as_deref());
+                    if !allowed {
+                        disallowed += 1;
+                    }
+                    vec![
+                        p.name.to_owned(),
+                        p.version.to_owned(),
+                        if allowed { "" } else { "not allowed" }.to_owned(),
+                        p.license.to_owned().unwrap_or_else(|| "unknown".into()),
+                    ]
+                })
+                .collect();
+            print_table(&["NAME", "VERSION", "POLICY", "LICENSE"], rows);
+            if disallowed > 0 {
+                return Err(anyhow!(
+                    "{disallowed} packages have licenses the policy doesn't allow"
+                ));
+            }
+        }
+        CliCommand::Fav { command } => {
+            let mut config = load_config()?;
+            match command {
+                FavCommand::List { json } => {
+                    if json {
+                        return print_json(&config.favourites.crates);
+                    }
+                    for id in config.favourites.crates {
+                        println!("{id}");
+                    }
+                }
+                FavCommand::Add { id } => {
+                    if !config.favourites.crates.contains(&id) {
+                        config.favourites.crates.push(id);
+                    }
+                    save_config(config)?;
+                }
+                FavCommand::Remove { id } => {
+                    config.favourites.crates.retain(|c| *c != id);
+                    save_config(config)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints a manifest change as a diff, or writes it after backing up Cargo.toml
+fn write_edit(edit: ManifestEdit, dry_run: bool) -> Result<()> {
+    if dry_run {
+        for line in edit.diff() {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+    let backup = edit.apply()?;
+    println!("{}, backed up to {}", edit.description, backup.display());
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Prints left aligned columns, the last column isn't padded
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut lines: Vec<Vec<String>> = vec![];
+    if !headers.is_empty() {
+        lines.push(headers.iter().map(|h| h.to_string()).collect());
+    }
+    lines.extend(rows);
+
+    let columns = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            lines
+                .iter()
+                .filter_map(|l| l.get(i))
+                .map(|c| c.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for line in lines {
+        let mut out = String::new();
+        for (i, cell) in line.iter().enumerate() {
+            if i + 1 == line.len() {
+                out.push_str(cell);
+            } else {
+                out.push_str(&format!("{cell:width$}  ", width = widths[i]));
+            }
+        }
+        println!("{}", out.trim_end());
+    }
+}
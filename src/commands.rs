@@ -0,0 +1,183 @@
+use crate::{
+    app::App,
+    cargo::{self, Sort},
+    config::Appearance,
+    pages::search,
+    theme::{theme_names, Theme},
+    ui::TAB_TITLES,
+};
+use anyhow::{anyhow, Result};
+
+pub const COMMAND_NAMES: [&str; 9] = [
+    "add",
+    "remove",
+    "install",
+    "tab",
+    "theme",
+    "sort",
+    "advisories",
+    "undo",
+    "redo",
+];
+
+/// A command typed into the command line such as `add serde@1 derive`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Add {
+        id: String,
+        version: Option<String>,
+        features: Vec<String>,
+    },
+    Remove(String),
+    Install(String),
+    Tab(u8),
+    Theme(String),
+    Sort(Sort),
+    /// Downloads or updates the advisory database
+    Advisories,
+    Undo,
+    Redo,
+}
+
+impl Command {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut words = input.split_whitespace();
+        let name = words.next().ok_or_else(|| anyhow!("No command given"))?;
+        let args: Vec<&str> = words.collect();
+
+        let single_arg = || match args.as_slice() {
+            [arg] => Ok(arg.to_string()),
+            [] => Err(anyhow!("'{name}' needs an argument")),
+            _ => Err(anyhow!("'{name}' takes one argument")),
+        };
+
+        Ok(match name {
+            "add" => {
+                let (spec, features) = args
+                    .split_first()
+                    .ok_or_else(|| anyhow!("'add' needs a crate"))?;
+                let (id, version) = match spec.split_once('@') {
+                    Some((id, version)) => (id.to_owned(), Some(version.to_owned())),
+                    None => (spec.to_string(), None),
+                };
+                let features = features
+                    .iter()
+                    .flat_map(|f| f.split(','))
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.to_owned())
+                    .collect();
+                Command::Add {
+                    id,
+                    version,
+                    features,
+                }
+            }
+            "remove" => Command::Remove(single_arg()?),
+            "install" => Command::Install(single_arg()?),
+            "tab" => {
+                let tab = single_arg()?;
+                let index = TAB_TITLES
+                    .iter()
+                    .position(|t| *t == tab || (tab == "favorites" && *t == "favourites"))
+                    .or_else(|| tab.parse::<usize>().ok().map(|n| n.wrapping_sub(1)))
+                    .filter(|i| *i < TAB_TITLES.len())
+                    .ok_or_else(|| anyhow!("Unknown tab '{tab}'"))?;
+                Command::Tab(index as u8)
+            }
+            "theme" => Command::Theme(single_arg()?),
+            "sort" => Command::Sort(single_arg()?.parse()?),
+            "advisories" => Command::Advisories,
+            "undo" => Command::Undo,
+            "redo" => Command::Redo,
+            _ => return Err(anyhow!("Unknown command '{name}'")),
+        })
+    }
+
+    pub fn run(self, app: &mut App) -> Result<String> {
+        match self {
+            Command::Add {
+                id,
+                version,
+                features,
+            } => {
+                app.add(id, version, features);
+                Ok(String::new())
+            }
+            Command::Remove(id) => {
+                app.preview_edit(cargo::remove(id), vec![]);
+                Ok(String::new())
+            }
+            Command::Undo => {
+                app.undo();
+                Ok(String::new())
+            }
+            Command::Redo => {
+                app.redo();
+                Ok(String::new())
+            }
+            Command::Install(id) => {
+                cargo::install(id.to_owned())?;
+                Ok(format!("Installing {id}"))
+            }
+            Command::Tab(tab) => {
+                app.tab = tab;
+                Ok(String::new())
+            }
+            Command::Theme(name) => {
+                let appearance = Appearance {
+                    theme: name.to_owned(),
+                    ..app.config.appearance
+                };
+                app.theme = Theme::load(&appearance, app.config.colors.as_ref())?;
+                app.config.appearance = appearance;
+                Ok(format!("Switched to the {name} theme"))
+            }
+            Command::Sort(sort) => {
+                app.config.search.sort = sort;
+                search::refresh(app);
+                Ok(format!("Sorting by {}", sort.as_str()))
+            }
+            Command::Advisories => {
+                app.refresh_advisories();
+                Ok("Updating the advisory database...".into())
+            }
+        }
+    }
+}
+
+/// Candidates for the word at the end of the input
+pub fn complete(app: &App, input: &str) -> Vec<String> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let current = if input.ends_with(' ') || input.is_empty() {
+        ""
+    } else {
+        words.last().copied().unwrap_or_default()
+    };
+    let position = if current.is_empty() {
+        words.len()
+    } else {
+        words.len() - 1
+    };
+
+    let candidates: Vec<String> = match (position, words.first().copied()) {
+        (0, _) => COMMAND_NAMES.iter().map(|c| c.to_string()).collect(),
+        (1, Some("add" | "install")) => {
+            let mut ids = app.config.favourites.crates.to_owned();
+            ids.extend(app.search_page.crate_ids());
+            ids
+        }
+        (1, Some("remove")) => cargo::dependencies().unwrap_or_default(),
+        (1, Some("tab")) => TAB_TITLES.iter().map(|t| t.to_string()).collect(),
+        (1, Some("theme")) => theme_names(),
+        (1, Some("sort")) => Sort::ALL.iter().map(|s| s.as_str().to_owned()).collect(),
+        _ => vec![],
+    };
+
+    let mut matching: Vec<String> = vec![];
+    for candidate in candidates {
+        if candidate.starts_with(current) && !matching.contains(&candidate) {
+            matching.push(candidate);
+        }
+    }
+    matching
+}
@@ -3,53 +3,237 @@ use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 use smart_default::SmartDefault;
 use std::{fs, path::PathBuf};
+use toml_edit::{Document, Item, Table};
 
-use crate::BINARY_NAME;
+use crate::{cargo::Sort, keymap::KeyBinding, BINARY_NAME};
 
+/// Anything missing from the config file comes from the defaults
 #[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub terminal: Terminal,
-    pub colors: Colors,
+    pub appearance: Appearance,
     pub favourites: Favourites,
     pub search: Search,
     pub mouse: Mouse,
+    pub keys: Keys,
+    pub advisories: Advisories,
+    pub policy: Policy,
+    /// Colours from before themes, still applied over the theme
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colors: Option<Colors>,
 }
 
 #[derive(SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Terminal {
+    /// Milliseconds between frames of animations like the loading spinner,
+    /// nothing is redrawn while idle
     #[default(250)]
-    pub tick_rate: u8,
+    pub tick_rate: u16,
 }
 
 #[derive(SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    /// One of the built-in themes or the name of a file in the themes directory
+    #[default("dark".into())]
+    pub theme: String,
+    pub palette: Palette,
+}
+
+/// The `[colors]` section from before themes. `primary` colours the title,
+/// selection and input, `warn` and `error` the messages, `secondary` was never used
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Colors {
-    #[default([238, 111, 248])]
-    pub primary: [u8; 3],
-    #[default([255, 0, 255])]
-    pub secondary: [u8; 3],
-    #[default([230, 126, 34])]
-    pub warn: [u8; 3],
-    #[default([255, 3, 3])]
-    pub error: [u8; 3],
+    pub primary: Option<[u8; 3]>,
+    pub secondary: Option<[u8; 3]>,
+    pub warn: Option<[u8; 3]>,
+    pub error: Option<[u8; 3]>,
+}
+
+/// The colours the terminal can display, rgb colours are downgraded to fit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    #[default]
+    Auto,
+    Truecolor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+#[derive(SmartDefault, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Advisories {
+    /// A clone of the RustSec advisory-db, defaults to one in the data directory
+    pub path: Option<PathBuf>,
+}
+
+#[derive(SmartDefault, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// SPDX ids like `MIT`, crates need a license expression these satisfy,
+    /// anything is allowed when empty
+    pub allowed_licenses: Vec<String>,
+    pub action: PolicyAction,
+}
+
+/// What happens when adding a crate the policy doesn't allow
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// Ask for confirmation first
+    #[default]
+    Warn,
+    Block,
 }
 
 #[derive(SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Favourites {
     pub crates: Vec<String>,
 }
 
 #[derive(SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Search {
     #[default(10)]
     pub max_pages: u8,
+    pub sort: Sort,
+    /// Search while typing instead of waiting for enter
+    pub live: bool,
+    /// How long typing has to pause before a live search starts
+    #[default(300)]
+    pub debounce_ms: u64,
+    /// How many past queries are remembered
+    #[default(100)]
+    pub history_size: usize,
 }
 
 #[derive(SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Mouse {
     #[default(true)]
     pub enabled: bool,
 }
 
+#[derive(SmartDefault, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keys {
+    // Global
+    #[default(KeyBinding::new(&["q", "esc"]))]
+    pub quit: KeyBinding,
+    #[default(KeyBinding::new(&["f4"]))]
+    pub force_quit: KeyBinding,
+    #[default(KeyBinding::new(&["tab"]))]
+    pub next_tab: KeyBinding,
+    #[default(KeyBinding::new(&["backtab"]))]
+    pub previous_tab: KeyBinding,
+    #[default(KeyBinding::new(&["1"]))]
+    pub search_tab: KeyBinding,
+    #[default(KeyBinding::new(&["2"]))]
+    pub manage_tab: KeyBinding,
+    #[default(KeyBinding::new(&["3"]))]
+    pub favourites_tab: KeyBinding,
+    #[default(KeyBinding::new(&["?"]))]
+    pub help: KeyBinding,
+    #[default(KeyBinding::new(&[":"]))]
+    pub command_line: KeyBinding,
+    #[default(KeyBinding::new(&["u"]))]
+    pub undo: KeyBinding,
+    #[default(KeyBinding::new(&["U"]))]
+    pub redo: KeyBinding,
+    #[default(KeyBinding::new(&["f12"]))]
+    pub debug: KeyBinding,
+    // Search
+    #[default(KeyBinding::new(&["s"]))]
+    pub search: KeyBinding,
+    #[default(KeyBinding::new(&["enter"]))]
+    pub details: KeyBinding,
+    #[default(KeyBinding::new(&["k", "up"]))]
+    pub up: KeyBinding,
+    #[default(KeyBinding::new(&["j", "down"]))]
+    pub down: KeyBinding,
+    #[default(KeyBinding::new(&["h", "left"]))]
+    pub previous_page: KeyBinding,
+    #[default(KeyBinding::new(&["l", "right"]))]
+    pub next_page: KeyBinding,
+    #[default(KeyBinding::new(&["c"]))]
+    pub open_crates_io: KeyBinding,
+    #[default(KeyBinding::new(&["d"]))]
+    pub open_docs: KeyBinding,
+    #[default(KeyBinding::new(&["g"]))]
+    pub open_repository: KeyBinding,
+    #[default(KeyBinding::new(&["a"]))]
+    pub add: KeyBinding,
+    #[default(KeyBinding::new(&["r"]))]
+    pub remove: KeyBinding,
+    #[default(KeyBinding::new(&["i"]))]
+    pub install: KeyBinding,
+    #[default(KeyBinding::new(&["f"]))]
+    pub favourite: KeyBinding,
+    #[default(KeyBinding::new(&["m"]))]
+    pub mark: KeyBinding,
+    #[default(KeyBinding::new(&["v"]))]
+    pub compare: KeyBinding,
+    #[default(KeyBinding::new(&["V"]))]
+    pub versions: KeyBinding,
+    // Manage
+    #[default(KeyBinding::new(&["L"]))]
+    pub licenses: KeyBinding,
+    #[default(KeyBinding::new(&["t"]))]
+    pub tree: KeyBinding,
+    #[default(KeyBinding::new(&["l", "right"]))]
+    pub expand: KeyBinding,
+    #[default(KeyBinding::new(&["h", "left"]))]
+    pub collapse: KeyBinding,
+    #[default(KeyBinding::new(&["D"]))]
+    pub duplicates: KeyBinding,
+    #[default(KeyBinding::new(&["b"]))]
+    pub bump: KeyBinding,
+    // Search box
+    #[default(KeyBinding::new(&["enter"]))]
+    pub submit: KeyBinding,
+    #[default(KeyBinding::new(&["esc"]))]
+    pub cancel: KeyBinding,
+    #[default(KeyBinding::new(&["up"]))]
+    pub history_previous: KeyBinding,
+    #[default(KeyBinding::new(&["down"]))]
+    pub history_next: KeyBinding,
+    #[default(KeyBinding::new(&["ctrl+r"]))]
+    pub history_search: KeyBinding,
+    // Command line
+    #[default(KeyBinding::new(&["tab"]))]
+    pub complete: KeyBinding,
+    // Text editing
+    #[default(KeyBinding::new(&["left"]))]
+    pub cursor_left: KeyBinding,
+    #[default(KeyBinding::new(&["right"]))]
+    pub cursor_right: KeyBinding,
+    #[default(KeyBinding::new(&["home", "ctrl+a"]))]
+    pub cursor_home: KeyBinding,
+    #[default(KeyBinding::new(&["end", "ctrl+e"]))]
+    pub cursor_end: KeyBinding,
+    #[default(KeyBinding::new(&["ctrl+left", "alt+b"]))]
+    pub word_left: KeyBinding,
+    #[default(KeyBinding::new(&["ctrl+right", "alt+f"]))]
+    pub word_right: KeyBinding,
+    #[default(KeyBinding::new(&["backspace"]))]
+    pub delete_back: KeyBinding,
+    #[default(KeyBinding::new(&["delete"]))]
+    pub delete_forward: KeyBinding,
+    #[default(KeyBinding::new(&["ctrl+w", "alt+backspace"]))]
+    pub delete_word_back: KeyBinding,
+    #[default(KeyBinding::new(&["ctrl+u"]))]
+    pub delete_to_start: KeyBinding,
+    #[default(KeyBinding::new(&["ctrl+k"]))]
+    pub delete_to_end: KeyBinding,
+}
+
 pub fn load_config() -> Result<Config> {
     let path = config_path()?;
     if !path.exists() {
@@ -60,22 +244,66 @@ pub fn load_config() -> Result<Config> {
         save_config(Config::default())?;
         return Ok(Config::default());
     }
-    let config_text = fs::read_to_string(path)?;
-    Ok(toml::from_str(&config_text)?)
+    let config_text = fs::read_to_string(&path)?;
+    // The error says where the mistake is
+    toml::from_str(&config_text).map_err(|e| anyhow!("Unable to load {}: {e}", path.display()))
 }
 
+/// Writes the config over the file's current values, keeping its comments
+/// and any keys this version doesn't know about
 pub fn save_config(config: Config) -> Result<()> {
-    let config_text = toml::to_string_pretty(&config)?;
-    fs::write(config_path()?, config_text)?;
+    let path = config_path()?;
+    let new: Document = toml::to_string_pretty(&config)?.parse()?;
+    let existing = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| text.parse::<Document>().ok());
+    let config_text = match existing {
+        Some(mut doc) => {
+            merge(doc.as_table_mut(), new.as_table());
+            doc.to_string()
+        }
+        None => new.to_string(),
+    };
+    fs::write(path, config_text)?;
     Ok(())
 }
 
+fn merge(old: &mut Table, new: &Table) {
+    for (key, item) in new.iter() {
+        match (old.get_mut(key), item) {
+            (Some(Item::Table(old)), Item::Table(new)) => merge(old, new),
+            (Some(Item::Value(old)), Item::Value(new)) => {
+                let decor = old.decor().clone();
+                *old = new.clone();
+                *old.decor_mut() = decor;
+            }
+            _ => {
+                old.insert(key, item.clone());
+            }
+        }
+    }
+}
+
 fn config_path() -> Result<PathBuf> {
+    let mut path = config_dir()?;
+    path.push("config.toml");
+    Ok(path)
+}
+
+pub fn data_dir() -> Result<PathBuf> {
+    let mut path = BaseDirs::new()
+        .ok_or_else(|| anyhow!("Failed to get data path"))?
+        .data_dir()
+        .to_path_buf();
+    path.push(BINARY_NAME);
+    Ok(path)
+}
+
+pub fn config_dir() -> Result<PathBuf> {
     let mut path = BaseDirs::new()
         .ok_or_else(|| anyhow!("Failed to get config path"))?
         .config_dir()
         .to_path_buf();
     path.push(BINARY_NAME);
-    path.push("config.toml");
     Ok(path)
 }
@@ -0,0 +1,70 @@
+use crate::config::data_dir;
+use anyhow::Result;
+use std::{fs, path::PathBuf};
+
+/// Past search queries, oldest first
+#[derive(Default, Debug)]
+pub struct History {
+    entries: Vec<String>,
+    size: usize,
+}
+
+impl History {
+    pub fn load(size: usize) -> Self {
+        let entries = history_path()
+            .and_then(|path| Ok(fs::read_to_string(path)?))
+            .map(|text| text.lines().map(|l| l.to_owned()).collect())
+            .unwrap_or_default();
+        let mut history = Self { entries, size };
+        history.truncate();
+        history
+    }
+
+    /// Adds a query as the most recent entry and saves the history
+    pub fn push(&mut self, query: &str) -> Result<()> {
+        let query = query.trim();
+        if query.is_empty() || self.size == 0 {
+            return Ok(());
+        }
+        self.entries.retain(|e| e != query);
+        self.entries.push(query.to_owned());
+        self.truncate();
+        self.save()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|e| e.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Index of the newest entry before `before` that contains `pattern`
+    pub fn find_before(&self, pattern: &str, before: usize) -> Option<usize> {
+        self.entries[..before.min(self.entries.len())]
+            .iter()
+            .rposition(|e| e.contains(pattern))
+    }
+
+    fn truncate(&mut self) {
+        if self.entries.len() > self.size {
+            self.entries.drain(..self.entries.len() - self.size);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = history_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.entries.join("\n"))?;
+        Ok(())
+    }
+}
+
+fn history_path() -> Result<PathBuf> {
+    let mut path = data_dir()?;
+    path.push("history");
+    Ok(path)
+}
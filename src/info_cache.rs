@@ -0,0 +1,53 @@
+use crate::{
+    cargo::InfoResponse,
+    worker::{Request, Response, Worker},
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Full crate info for crates on screen, search results leave out things like
+/// licenses so they're fetched in the background
+#[derive(Default, Debug)]
+pub struct InfoCache {
+    /// The error when fetching failed, so it isn't retried every time it's shown
+    entries: HashMap<String, Result<InfoResponse, String>>,
+}
+
+impl InfoCache {
+    pub fn get(&self, id: &str) -> Option<&InfoResponse> {
+        self.entries.get(id).and_then(|e| e.as_ref().ok())
+    }
+
+    /// Why fetching failed
+    pub fn error(&self, id: &str) -> Option<&str> {
+        self.entries
+            .get(id)
+            .and_then(|e| e.as_ref().err())
+            .map(String::as_str)
+    }
+
+    /// Whether fetching was tried, whether or not it worked
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    /// Fetches crates that haven't been fetched yet in the background, behind
+    /// anything else the worker is doing. Replaces the crates asked for last time
+    pub fn prefetch(&self, worker: &mut Worker, ids: &[String]) {
+        let requests = ids
+            .iter()
+            .filter(|id| !self.entries.contains_key(*id))
+            .map(|id| Request::Info(id.to_owned()))
+            .collect();
+        worker.prefetch(requests);
+    }
+
+    pub fn receive(&mut self, id: String, response: Result<Response>) {
+        let info = match response {
+            Ok(Response::Info(info)) => Ok(*info),
+            Ok(_) => Err("Unexpected response".to_owned()),
+            Err(e) => Err(e.to_string()),
+        };
+        self.entries.insert(id, info);
+    }
+}
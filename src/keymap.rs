@@ -0,0 +1,591 @@
+use crate::config::Keys;
+use anyhow::{anyhow, Error, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// The actions available in one part of the app, handlers only look up
+/// actions listed in their context so the help menu can't drift from them
+pub struct Context {
+    pub name: &'static str,
+    pub actions: &'static [Action],
+}
+
+pub const GLOBAL: Context = Context {
+    name: "Global",
+    actions: &[
+        Action::Quit,
+        Action::ForceQuit,
+        Action::NextTab,
+        Action::PreviousTab,
+        Action::SearchTab,
+        Action::ManageTab,
+        Action::FavouritesTab,
+        Action::Help,
+        Action::CommandLine,
+        Action::Undo,
+        Action::Redo,
+        Action::Debug,
+    ],
+};
+
+pub const SEARCH: Context = Context {
+    name: "Search results",
+    actions: &[
+        Action::Search,
+        Action::Details,
+        Action::Up,
+        Action::Down,
+        Action::PreviousPage,
+        Action::NextPage,
+        Action::OpenCratesIo,
+        Action::OpenDocs,
+        Action::OpenRepository,
+        Action::Add,
+        Action::Versions,
+        Action::Remove,
+        Action::Install,
+        Action::Favourite,
+        Action::Mark,
+        Action::Compare,
+    ],
+};
+
+pub const MANAGE: Context = Context {
+    name: "Project dependencies",
+    actions: &[
+        Action::Up,
+        Action::Down,
+        Action::Details,
+        Action::Expand,
+        Action::Collapse,
+        Action::OpenCratesIo,
+        Action::OpenDocs,
+        Action::Remove,
+        Action::Versions,
+        Action::Licenses,
+        Action::Tree,
+        Action::Duplicates,
+        Action::Bump,
+    ],
+};
+
+pub const FAVOURITES: Context = Context {
+    name: "Favourites",
+    actions: &[
+        Action::Up,
+        Action::Down,
+        Action::OpenCratesIo,
+        Action::OpenDocs,
+        Action::Install,
+        Action::Versions,
+        Action::Favourite,
+        Action::Mark,
+        Action::Compare,
+    ],
+};
+
+pub const COMPARE: Context = Context {
+    name: "Comparison",
+    actions: &[Action::Cancel, Action::Compare],
+};
+
+pub const VERSIONS: Context = Context {
+    name: "Versions",
+    actions: &[Action::Up, Action::Down, Action::Submit, Action::Cancel],
+};
+
+pub const SEARCH_BOX: Context = Context {
+    name: "Search box",
+    actions: &[
+        Action::Submit,
+        Action::Cancel,
+        Action::HistoryPrevious,
+        Action::HistoryNext,
+        Action::HistorySearch,
+    ],
+};
+
+pub const COMMAND_LINE: Context = Context {
+    name: "Command line",
+    actions: &[Action::Submit, Action::Cancel, Action::Complete],
+};
+
+/// Shared by every text input, looked up after the input's own context
+pub const EDITING: Context = Context {
+    name: "Text editing",
+    actions: &[
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::CursorHome,
+        Action::CursorEnd,
+        Action::WordLeft,
+        Action::WordRight,
+        Action::DeleteBack,
+        Action::DeleteForward,
+        Action::DeleteWordBack,
+        Action::DeleteToStart,
+        Action::DeleteToEnd,
+    ],
+};
+
+pub const DETAILS: Context = Context {
+    name: "Crate details",
+    actions: &[
+        Action::Cancel,
+        Action::OpenCratesIo,
+        Action::OpenDocs,
+        Action::OpenRepository,
+        Action::Add,
+        Action::Versions,
+        Action::Install,
+        Action::Favourite,
+    ],
+};
+
+pub const PREVIEW: Context = Context {
+    name: "Manifest change",
+    actions: &[Action::Up, Action::Down, Action::Submit, Action::Cancel],
+};
+
+pub const HELP: Context = Context {
+    name: "Help menu",
+    actions: &[Action::Up, Action::Down, Action::Help, Action::Cancel],
+};
+
+/// Every context in the order they're shown in the help menu
+pub const CONTEXTS: [&Context; 12] = [
+    &GLOBAL,
+    &SEARCH,
+    &SEARCH_BOX,
+    &DETAILS,
+    &MANAGE,
+    &FAVOURITES,
+    &COMPARE,
+    &VERSIONS,
+    &PREVIEW,
+    &COMMAND_LINE,
+    &EDITING,
+    &HELP,
+];
+
+/// Everything a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    // Global
+    Quit,
+    ForceQuit,
+    NextTab,
+    PreviousTab,
+    SearchTab,
+    ManageTab,
+    FavouritesTab,
+    Help,
+    CommandLine,
+    Undo,
+    Redo,
+    Debug,
+    // Search
+    Search,
+    Details,
+    Up,
+    Down,
+    PreviousPage,
+    NextPage,
+    OpenCratesIo,
+    OpenDocs,
+    OpenRepository,
+    Add,
+    Remove,
+    Install,
+    Favourite,
+    Mark,
+    Compare,
+    Versions,
+    // Manage
+    Licenses,
+    Tree,
+    Expand,
+    Collapse,
+    Duplicates,
+    Bump,
+    // Search box
+    Submit,
+    Cancel,
+    HistoryPrevious,
+    HistoryNext,
+    HistorySearch,
+    // Command line
+    Complete,
+    // Text editing
+    CursorLeft,
+    CursorRight,
+    CursorHome,
+    CursorEnd,
+    WordLeft,
+    WordRight,
+    DeleteBack,
+    DeleteForward,
+    DeleteWordBack,
+    DeleteToStart,
+    DeleteToEnd,
+}
+
+impl Action {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ForceQuit => "force quit",
+            Action::NextTab => "next tab",
+            Action::PreviousTab => "previous tab",
+            Action::SearchTab => "search tab",
+            Action::ManageTab => "manage tab",
+            Action::FavouritesTab => "favourites tab",
+            Action::Search => "search",
+            Action::Details => "details",
+            Action::Help => "help",
+            Action::CommandLine => "command line",
+            Action::Undo => "undo manifest change",
+            Action::Redo => "redo manifest change",
+            Action::Debug => "debug panel",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PreviousPage => "previous page",
+            Action::NextPage => "next page",
+            Action::OpenCratesIo => "crates.io",
+            Action::OpenDocs => "docs.rs",
+            Action::OpenRepository => "git repo",
+            Action::Add => "add",
+            Action::Remove => "remove",
+            Action::Install => "install",
+            Action::Favourite => "favourite",
+            Action::Mark => "mark to compare",
+            Action::Compare => "compare marked",
+            Action::Versions => "versions",
+            Action::Licenses => "license report",
+            Action::Tree => "dependency tree",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::Duplicates => "duplicates",
+            Action::Bump => "apply bump",
+            Action::Submit => "submit",
+            Action::Cancel => "cancel",
+            Action::CursorLeft => "cursor left",
+            Action::CursorRight => "cursor right",
+            Action::DeleteBack => "delete back",
+            Action::DeleteForward => "delete forward",
+            Action::CursorHome => "start of line",
+            Action::CursorEnd => "end of line",
+            Action::WordLeft => "previous word",
+            Action::WordRight => "next word",
+            Action::DeleteWordBack => "delete word",
+            Action::DeleteToStart => "delete to start",
+            Action::DeleteToEnd => "delete to end",
+            Action::HistoryPrevious => "older query",
+            Action::HistoryNext => "newer query",
+            Action::HistorySearch => "search history",
+            Action::Complete => "complete",
+        }
+    }
+}
+
+impl Keys {
+    pub fn get(&self, action: Action) -> &KeyBinding {
+        match action {
+            Action::Quit => &self.quit,
+            Action::ForceQuit => &self.force_quit,
+            Action::NextTab => &self.next_tab,
+            Action::PreviousTab => &self.previous_tab,
+            Action::SearchTab => &self.search_tab,
+            Action::ManageTab => &self.manage_tab,
+            Action::FavouritesTab => &self.favourites_tab,
+            Action::Search => &self.search,
+            Action::Details => &self.details,
+            Action::Help => &self.help,
+            Action::CommandLine => &self.command_line,
+            Action::Undo => &self.undo,
+            Action::Redo => &self.redo,
+            Action::Debug => &self.debug,
+            Action::Up => &self.up,
+            Action::Down => &self.down,
+            Action::PreviousPage => &self.previous_page,
+            Action::NextPage => &self.next_page,
+            Action::OpenCratesIo => &self.open_crates_io,
+            Action::OpenDocs => &self.open_docs,
+            Action::OpenRepository => &self.open_repository,
+            Action::Add => &self.add,
+            Action::Remove => &self.remove,
+            Action::Install => &self.install,
+            Action::Favourite => &self.favourite,
+            Action::Mark => &self.mark,
+            Action::Compare => &self.compare,
+            Action::Versions => &self.versions,
+            Action::Licenses => &self.licenses,
+            Action::Tree => &self.tree,
+            Action::Expand => &self.expand,
+            Action::Collapse => &self.collapse,
+            Action::Duplicates => &self.duplicates,
+            Action::Bump => &self.bump,
+            Action::Submit => &self.submit,
+            Action::Cancel => &self.cancel,
+            Action::CursorLeft => &self.cursor_left,
+            Action::CursorRight => &self.cursor_right,
+            Action::DeleteBack => &self.delete_back,
+            Action::DeleteForward => &self.delete_forward,
+            Action::CursorHome => &self.cursor_home,
+            Action::CursorEnd => &self.cursor_end,
+            Action::WordLeft => &self.word_left,
+            Action::WordRight => &self.word_right,
+            Action::DeleteWordBack => &self.delete_word_back,
+            Action::DeleteToStart => &self.delete_to_start,
+            Action::DeleteToEnd => &self.delete_to_end,
+            Action::HistoryPrevious => &self.history_previous,
+            Action::HistoryNext => &self.history_next,
+            Action::HistorySearch => &self.history_search,
+            Action::Complete => &self.complete,
+        }
+    }
+
+    /// Returns the first of `actions` bound to the key
+    pub fn find(&self, key: &KeyEvent, actions: &[Action]) -> Option<Action> {
+        actions
+            .iter()
+            .copied()
+            .find(|action| self.get(*action).matches(key))
+    }
+}
+
+/// All the chords bound to a single action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyBinding(pub Vec<KeyChord>);
+
+impl KeyBinding {
+    pub fn new(chords: &[&str]) -> Self {
+        Self(
+            chords
+                .iter()
+                .map(|c| c.parse().expect("Invalid default key chord"))
+                .collect(),
+        )
+    }
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        self.0.iter().any(|chord| chord.matches(key))
+    }
+    /// The chord shown in the help footer
+    pub fn primary(&self) -> String {
+        self.0
+            .first()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "none".into())
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chords: Vec<String> = self.0.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", chords.join("/"))
+    }
+}
+
+/// A key plus modifiers, written as `ctrl+alt+k` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let (code, modifiers) = normalize(key.code, key.modifiers);
+        self.code == code && self.modifiers == modifiers
+    }
+}
+
+/// Shift is already part of upper case chars and back tab so it's ignored for them
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(_) | KeyCode::BackTab => (code, modifiers - KeyModifiers::SHIFT),
+        _ => (code, modifiers),
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = s.split('+').collect();
+        // A trailing empty part means the key itself is '+'
+        let key = match parts.pop() {
+            Some("") if s.ends_with('+') => {
+                parts.pop();
+                "+"
+            }
+            Some(key) => key,
+            None => return Err(anyhow!("Empty key chord")),
+        };
+
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow!("Unknown modifier '{modifier}' in '{s}'")),
+            };
+        }
+
+        let code = if key.chars().count() == 1 {
+            KeyCode::Char(key.chars().next().unwrap())
+        } else {
+            match key.to_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f if f.starts_with('f') => KeyCode::F(
+                    f[1..]
+                        .parse()
+                        .map_err(|_| anyhow!("Unknown key '{key}' in '{s}'"))?,
+                ),
+                _ => return Err(anyhow!("Unknown key '{key}' in '{s}'")),
+            }
+        };
+
+        // Terminals send shifted letters as upper case, so `shift+a` is `A`
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+            }
+            _ => code,
+        };
+        let (code, modifiers) = normalize(code, modifiers);
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(chord: KeyChord) -> Self {
+        chord.to_string()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord { code, modifiers }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let cases = [
+            ("a", chord(KeyCode::Char('a'), KeyModifiers::NONE)),
+            ("A", chord(KeyCode::Char('A'), KeyModifiers::NONE)),
+            ("shift+a", chord(KeyCode::Char('A'), KeyModifiers::NONE)),
+            ("shift+A", chord(KeyCode::Char('A'), KeyModifiers::NONE)),
+            ("ctrl+r", chord(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            (
+                "Ctrl+Alt+k",
+                chord(
+                    KeyCode::Char('k'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT,
+                ),
+            ),
+            ("+", chord(KeyCode::Char('+'), KeyModifiers::NONE)),
+            ("ctrl++", chord(KeyCode::Char('+'), KeyModifiers::CONTROL)),
+            ("space", chord(KeyCode::Char(' '), KeyModifiers::NONE)),
+            ("Enter", chord(KeyCode::Enter, KeyModifiers::NONE)),
+            ("shift+tab", chord(KeyCode::Tab, KeyModifiers::SHIFT)),
+            ("backtab", chord(KeyCode::BackTab, KeyModifiers::NONE)),
+            ("f12", chord(KeyCode::F(12), KeyModifiers::NONE)),
+            ("alt+pagedown", chord(KeyCode::PageDown, KeyModifiers::ALT)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(text.parse::<KeyChord>().unwrap(), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_chords() {
+        for text in ["", "hyper+a", "foo", "fx", "ctrl+shift"] {
+            assert!(text.parse::<KeyChord>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn shifted_letters_match_what_terminals_send() {
+        let binding: KeyChord = "shift+a".parse().unwrap();
+        assert!(binding.matches(&KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT)));
+        assert!(!binding.matches(&KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        for text in ["a", "A", "ctrl+alt+k", "shift+tab", "space", "f5", "ctrl++"] {
+            let chord: KeyChord = text.parse().unwrap();
+            assert_eq!(
+                chord.to_string().parse::<KeyChord>().unwrap(),
+                chord,
+                "{text}"
+            );
+        }
+    }
+}
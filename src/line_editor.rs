@@ -0,0 +1,319 @@
+use crate::keymap::Action;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A single line of editable text for the search box and command line
+#[derive(Default, Debug, Clone)]
+pub struct LineEditor {
+    text: String,
+    /// Byte index into `text`, always on a grapheme boundary
+    cursor: usize,
+    /// Display column of the first visible column when the text is wider than the box
+    scroll: usize,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// Replaces the text and moves the cursor to the end
+    pub fn set_text(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        self.scroll = 0;
+        std::mem::take(&mut self.text)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+    /// Display width of the whole text
+    pub fn width(&self) -> usize {
+        self.text.width()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+    /// Inserts pasted text, line breaks become spaces as there's only one line
+    pub fn insert_str(&mut self, s: &str) {
+        let s: String = s
+            .chars()
+            .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+            .filter(|c| !c.is_control())
+            .collect();
+        self.text.insert_str(self.cursor, &s);
+        self.cursor += s.len();
+    }
+
+    fn previous_boundary(&self, from: usize) -> usize {
+        self.text[..from]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+    fn next_boundary(&self, from: usize) -> usize {
+        self.text[from..]
+            .graphemes(true)
+            .next()
+            .map_or(from, |g| from + g.len())
+    }
+    /// Start of the word before `from`, skipping any separators first
+    fn previous_word(&self, from: usize) -> usize {
+        let mut graphemes = self.text[..from].grapheme_indices(true).rev().peekable();
+        while graphemes.next_if(|(_, g)| !is_word(g)).is_some() {}
+        let mut start = graphemes.peek().map_or(0, |(i, g)| i + g.len());
+        while let Some((i, _)) = graphemes.next_if(|(_, g)| is_word(g)) {
+            start = i;
+        }
+        start
+    }
+    /// End of the word after `from`, skipping any separators first
+    fn next_word(&self, from: usize) -> usize {
+        let mut graphemes = self.text[from..].grapheme_indices(true).peekable();
+        while graphemes.next_if(|(_, g)| !is_word(g)).is_some() {}
+        while graphemes.next_if(|(_, g)| is_word(g)).is_some() {}
+        graphemes.peek().map_or(self.text.len(), |(i, _)| from + i)
+    }
+
+    /// Runs an editing action or inserts a typed char, returns false if the key isn't for editing
+    pub fn handle(&mut self, action: Option<Action>, key: &KeyEvent) -> bool {
+        match action {
+            Some(Action::CursorLeft) => self.cursor = self.previous_boundary(self.cursor),
+            Some(Action::CursorRight) => self.cursor = self.next_boundary(self.cursor),
+            Some(Action::CursorHome) => self.cursor = 0,
+            Some(Action::CursorEnd) => self.cursor = self.text.len(),
+            Some(Action::WordLeft) => self.cursor = self.previous_word(self.cursor),
+            Some(Action::WordRight) => self.cursor = self.next_word(self.cursor),
+            Some(Action::DeleteBack) => {
+                let start = self.previous_boundary(self.cursor);
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            Some(Action::DeleteForward) => {
+                let end = self.next_boundary(self.cursor);
+                self.text.replace_range(self.cursor..end, "");
+            }
+            Some(Action::DeleteWordBack) => {
+                let start = self.previous_word(self.cursor);
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            Some(Action::DeleteToStart) => {
+                self.text.replace_range(..self.cursor, "");
+                self.cursor = 0;
+            }
+            Some(Action::DeleteToEnd) => self.text.truncate(self.cursor),
+            _ => match key.code {
+                KeyCode::Char(c)
+                    if !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                {
+                    self.insert(c)
+                }
+                _ => return false,
+            },
+        }
+        true
+    }
+
+    /// The part of the text that fits in `width` columns and the cursor's column within it,
+    /// scrolling horizontally to keep the cursor in view
+    pub fn view(&mut self, width: usize) -> (String, usize) {
+        let cursor_column = self.text[..self.cursor].width();
+        let width = width.max(1);
+        if cursor_column < self.scroll {
+            self.scroll = cursor_column;
+        } else if cursor_column >= self.scroll + width {
+            self.scroll = cursor_column + 1 - width;
+        }
+        // Don't leave empty space on the right when deleting from the end,
+        // one column past the text is kept for the cursor
+        self.scroll = self.scroll.min((self.width() + 1).saturating_sub(width));
+
+        let mut visible = String::new();
+        let mut column = 0;
+        for g in self.text.graphemes(true) {
+            let g_width = g.width();
+            if column >= self.scroll && column + g_width <= self.scroll + width {
+                visible.push_str(g);
+            } else if column + g_width > self.scroll + width {
+                break;
+            }
+            column += g_width;
+        }
+
+        // A wide grapheme cut off at the left edge shifts the text by one column
+        let skipped: usize = self
+            .text
+            .graphemes(true)
+            .scan(0, |column, g| {
+                let start = *column;
+                *column += g.width();
+                Some(start)
+            })
+            .find(|start| *start >= self.scroll)
+            .map_or(0, |start| start - self.scroll);
+
+        (
+            format!("{}{visible}", " ".repeat(skipped)),
+            cursor_column - self.scroll,
+        )
+    }
+}
+
+fn is_word(g: &str) -> bool {
+    g.chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        editor.set_text(text.into());
+        editor
+    }
+
+    fn run(editor: &mut LineEditor, action: Action) {
+        let key = KeyEvent::new(KeyCode::Null, KeyModifiers::NONE);
+        assert!(editor.handle(Some(action), &key));
+    }
+
+    #[test]
+    fn combining_marks_move_and_delete_as_one() {
+        // "e" followed by a combining acute accent
+        let mut e = editor("cafe\u{301}s");
+        run(&mut e, Action::CursorLeft);
+        run(&mut e, Action::CursorLeft);
+        assert_eq!(e.cursor, 3);
+        run(&mut e, Action::DeleteForward);
+        assert_eq!(e.text(), "cafs");
+        run(&mut e, Action::CursorEnd);
+        run(&mut e, Action::DeleteBack);
+        run(&mut e, Action::DeleteBack);
+        assert_eq!(e.text(), "ca");
+    }
+
+    #[test]
+    fn wide_chars_take_two_columns() {
+        let mut e = editor("日本語");
+        assert_eq!(e.width(), 6);
+        assert_eq!(e.view(10), ("日本語".into(), 6));
+        run(&mut e, Action::CursorLeft);
+        assert_eq!(e.view(10), ("日本語".into(), 4));
+        run(&mut e, Action::DeleteBack);
+        assert_eq!(e.text(), "日語");
+        assert_eq!(e.view(10).1, 2);
+    }
+
+    #[test]
+    fn typing_inserts_at_the_cursor() {
+        let mut e = editor("ab");
+        run(&mut e, Action::CursorLeft);
+        let key = KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE);
+        assert!(e.handle(None, &key));
+        assert_eq!(e.text(), "aéb");
+        let ctrl = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert!(!e.handle(None, &ctrl));
+        assert_eq!(e.text(), "aéb");
+    }
+
+    #[test]
+    fn pasted_line_breaks_become_spaces() {
+        let mut e = editor("");
+        e.insert_str("serde\njson\r\t!");
+        assert_eq!(e.text(), "serde json !");
+        assert_eq!(e.cursor, e.text().len());
+    }
+
+    #[test]
+    fn word_jumps_skip_punctuation() {
+        let text = "serde::de, tokio-util  x_y";
+        let cases = [
+            (text.len(), Action::WordLeft, 23),
+            (23, Action::WordLeft, 17),
+            (17, Action::WordLeft, 11),
+            (11, Action::WordLeft, 7),
+            (9, Action::WordLeft, 7),
+            (7, Action::WordLeft, 0),
+            (0, Action::WordLeft, 0),
+            (0, Action::WordRight, 5),
+            (5, Action::WordRight, 9),
+            (9, Action::WordRight, 16),
+            (16, Action::WordRight, 21),
+            (21, Action::WordRight, text.len()),
+            (text.len(), Action::WordRight, text.len()),
+        ];
+        for (from, action, to) in cases {
+            let mut e = editor(text);
+            e.cursor = from;
+            run(&mut e, action);
+            assert_eq!(e.cursor, to, "{action:?} from {from}");
+        }
+    }
+
+    #[test]
+    fn word_jumps_treat_accented_words_as_words() {
+        let mut e = editor("naïve café");
+        run(&mut e, Action::WordLeft);
+        assert_eq!(&e.text()[e.cursor..], "café");
+        run(&mut e, Action::DeleteWordBack);
+        assert_eq!(e.text(), "café");
+    }
+
+    #[test]
+    fn deletes_to_either_end() {
+        let mut e = editor("hello world");
+        e.cursor = 5;
+        run(&mut e, Action::DeleteToEnd);
+        assert_eq!(e.text(), "hello");
+        e.cursor = 2;
+        run(&mut e, Action::DeleteToStart);
+        assert_eq!((e.text(), e.cursor), ("llo", 0));
+    }
+
+    #[test]
+    fn scrolls_to_keep_the_cursor_in_view() {
+        let mut e = editor("abcdefghij");
+        // The cursor past the end needs a column of its own
+        assert_eq!(e.view(5), ("ghij".into(), 4));
+        run(&mut e, Action::CursorHome);
+        assert_eq!(e.view(5), ("abcde".into(), 0));
+        e.cursor = 7;
+        assert_eq!(e.view(5), ("defgh".into(), 4));
+        run(&mut e, Action::CursorLeft);
+        assert_eq!(e.view(5), ("defgh".into(), 3));
+    }
+
+    #[test]
+    fn scroll_is_clamped_when_text_shrinks() {
+        let mut e = editor("abcdefghij");
+        e.view(5);
+        run(&mut e, Action::DeleteBack);
+        run(&mut e, Action::DeleteBack);
+        run(&mut e, Action::DeleteBack);
+        assert_eq!(e.view(5), ("defg".into(), 4));
+        e.take();
+        assert_eq!(e.view(5), (String::new(), 0));
+        // A zero width box still has room for the cursor
+        assert_eq!(editor("abc").view(0), (String::new(), 0));
+    }
+
+    #[test]
+    fn wide_chars_cut_off_on_the_left_are_padded() {
+        let mut e = editor("a日本");
+        // Five columns plus the cursor doesn't fit in four, so one is scrolled off
+        assert_eq!(e.view(4), (" 本".into(), 3));
+        e.cursor = 0;
+        assert_eq!(e.view(4), ("a日".into(), 0));
+    }
+}
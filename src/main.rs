@@ -1,49 +1,88 @@
 use anyhow::Result;
 use app::{run_app, App};
-use config::{load_config, save_config};
+use clap::Parser;
+use cli::Cli;
+use config::{load_config, save_config, Config};
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{io, time::Duration};
 use tui::{backend::CrosstermBackend, Terminal};
 
+mod advisories;
 mod app;
 mod cargo;
+mod cli;
+mod commands;
 mod config;
+mod history;
+mod info_cache;
+mod keymap;
+mod line_editor;
 mod pages;
+mod policy;
+mod theme;
 mod ui;
+mod worker;
 
 const BINARY_NAME: &str = "cratui";
 
 fn main() -> Result<()> {
+    // Commands run without the TUI
+    let args = Cli::parse();
+    if let Some(dir) = args.registry_fixtures {
+        cargo::use_registry(cargo::Fixtures::new(dir)?)?;
+    }
+    if let Some(command) = args.command {
+        return cli::run(command);
+    }
+
     // Setup Terminal
+    let (config, config_error) = match load_config() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
+    if config.mouse.enabled {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Create and run App
-    let tick_rate = Duration::from_millis(250);
-    let mut app = App::new(load_config().unwrap_or_default());
+    let tick_rate = Duration::from_millis(config.terminal.tick_rate.max(1) as u64);
+    let mut app = App::new(config);
+    if let Some(e) = &config_error {
+        app.error();
+        app.command_line
+            .sticky_error(format!("{e}, it won't be saved until it's fixed"));
+    }
     let res = run_app(&mut terminal, &mut app, tick_rate);
 
-    // Save Config
-    if let Err(e) = save_config(app.config) {
-        println!("Error saving config!\n{e:#?}");
-    }
+    // Save Config, a file that failed to load is left alone rather than
+    // replaced with the defaults
+    let saved = match config_error {
+        Some(e) => Err(e),
+        None => save_config(app.config),
+    };
 
     // Restore Terminal
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
+        DisableBracketedPaste,
         DisableMouseCapture,
     )?;
     terminal.show_cursor()?;
 
+    if let Err(e) = saved {
+        println!("Config wasn't saved: {e}");
+    }
     if let Err(err) = res {
         println!("{:?}", err)
     }
@@ -0,0 +1,194 @@
+use crate::{
+    app::App,
+    commands::{self, Command},
+    keymap::{self, Action},
+    line_editor::LineEditor,
+};
+use crossterm::event::Event;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+#[derive(Default, Debug)]
+pub struct CommandLine {
+    pub active: bool,
+    input: LineEditor,
+    completion: Option<Completion>,
+    message: Option<Message>,
+    /// An error shown whenever there's no other message, until quitting
+    sticky: Option<String>,
+}
+
+/// Cycles through the candidates for the input as it was before completing
+#[derive(Debug)]
+struct Completion {
+    base: String,
+    candidates: Vec<String>,
+    index: usize,
+}
+
+#[derive(Debug)]
+enum Message {
+    Info(String),
+    Error(String),
+}
+
+impl CommandLine {
+    pub fn open(&mut self) {
+        *self = Self {
+            active: true,
+            sticky: self.sticky.take(),
+            ..Default::default()
+        };
+    }
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+    /// Shows a message on the bottom line until the next key press
+    pub fn info(&mut self, message: String) {
+        self.message = Some(Message::Info(message));
+    }
+    pub fn error(&mut self, message: String) {
+        self.message = Some(Message::Error(message));
+    }
+    /// Shows an error that stays until quitting, behind any other message
+    pub fn sticky_error(&mut self, message: String) {
+        self.sticky = Some(message);
+    }
+    /// `candidates` are only used when starting a new completion
+    fn complete(&mut self, candidates: Vec<String>) {
+        let completion = match self.completion.take() {
+            Some(mut completion) => {
+                completion.index = (completion.index + 1) % completion.candidates.len();
+                completion
+            }
+            None => {
+                if candidates.is_empty() {
+                    return;
+                }
+                Completion {
+                    base: self.input.text().to_owned(),
+                    candidates,
+                    index: 0,
+                }
+            }
+        };
+
+        // Replace the partial word at the end with the candidate
+        let start = completion
+            .base
+            .rfind(' ')
+            .map(|i| i + 1)
+            .unwrap_or_default();
+        let mut input = format!(
+            "{}{}",
+            &completion.base[..start],
+            completion.candidates[completion.index]
+        );
+        if completion.candidates.len() == 1 {
+            input.push(' ');
+        } else {
+            self.completion = Some(completion);
+        }
+        self.input.set_text(input);
+    }
+}
+
+/// The command line is modal so it consumes every event while active
+pub fn event(app: &mut App, e: Event) {
+    let key = match e {
+        Event::Key(key) => key,
+        Event::Paste(text) => {
+            app.command_line.completion = None;
+            app.command_line.input.insert_str(&text);
+            return;
+        }
+        _ => return,
+    };
+
+    let action = app
+        .config
+        .keys
+        .find(&key, keymap::COMMAND_LINE.actions)
+        .or_else(|| app.config.keys.find(&key, keymap::EDITING.actions));
+    if action != Some(Action::Complete) {
+        app.command_line.completion = None;
+    }
+
+    match action {
+        Some(Action::Complete) => {
+            let candidates = match app.command_line.completion {
+                Some(_) => vec![],
+                None => commands::complete(app, app.command_line.input.text()),
+            };
+            app.command_line.complete(candidates);
+        }
+        Some(Action::Submit) => {
+            let input = app.command_line.input.take();
+            app.command_line.active = false;
+            let result = Command::parse(&input).and_then(|command| command.run(app));
+            app.command_line.message = match result {
+                // Keeps any message the command showed itself
+                Ok(message) if message.is_empty() => app.command_line.message.take(),
+                Ok(message) => Some(Message::Info(message)),
+                Err(e) => {
+                    app.error();
+                    Some(Message::Error(e.to_string()))
+                }
+            };
+        }
+        Some(Action::Cancel) => app.command_line.active = false,
+        // Backspace on an empty line closes it like in vim
+        Some(Action::DeleteBack) if app.command_line.input.is_empty() => {
+            app.command_line.active = false
+        }
+        action => {
+            app.command_line.input.handle(action, &key);
+        }
+    }
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let state = &mut app.command_line;
+    let theme = &app.theme;
+
+    // The prompt takes a column and the candidates get at least a third of the line
+    let input_width = match state.completion {
+        Some(_) => area.width as usize * 2 / 3,
+        None => area.width as usize,
+    };
+    let (input, cursor) = state.input.view(input_width.saturating_sub(1));
+
+    let line = if state.active {
+        let mut spans = vec![
+            Span::styled(":", theme.input),
+            Span::styled(input, theme.text),
+        ];
+        if let Some(completion) = &state.completion {
+            spans.push(Span::styled(
+                format!("   {}", completion.candidates.join(" ")),
+                theme.dim_text,
+            ));
+        }
+        Spans::from(spans)
+    } else {
+        match &state.message {
+            Some(Message::Info(message)) => Spans::from(Span::styled(message, theme.dim_text)),
+            Some(Message::Error(message)) => Spans::from(Span::styled(message, theme.error)),
+            None => match &state.sticky {
+                Some(message) => Spans::from(Span::styled(message, theme.error)),
+                None => return,
+            },
+        }
+    };
+
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(line), area);
+    if state.active {
+        f.set_cursor(area.x + cursor as u16 + 1, area.y);
+    }
+}
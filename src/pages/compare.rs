@@ -0,0 +1,206 @@
+use crate::{
+    app::App,
+    cargo,
+    keymap::{self, Action},
+    ui::centered_rect,
+    worker::{Request, Response},
+};
+use anyhow::{anyhow, Result};
+use crossterm::event::Event;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Rect},
+    text::Span,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table},
+    Frame,
+};
+
+pub const MAX_MARKED: usize = 4;
+
+const FIELDS: [&str; 9] = [
+    "Latest version",
+    "Released",
+    "Downloads",
+    "Recent downloads",
+    "License",
+    "Dependencies",
+    "Dependents",
+    "MSRV",
+    "Features",
+];
+
+#[derive(Default, Debug)]
+pub struct ComparePage {
+    /// Crates marked from the search results or favourites, in the order they were marked
+    pub marked: Vec<String>,
+    open: bool,
+    columns: Vec<Result<Column>>,
+    request: Option<Request>,
+}
+
+impl ComparePage {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+    pub fn is_marked(&self, id: &str) -> bool {
+        self.marked.iter().any(|m| m == id)
+    }
+}
+
+/// One crate's values for each of `FIELDS`
+#[derive(Debug)]
+pub struct Column {
+    id: String,
+    values: [String; FIELDS.len()],
+}
+
+impl Column {
+    pub fn fetch(id: &str) -> Result<Self> {
+        let info = cargo::info(id)?;
+        let version = info
+            .latest()
+            .ok_or_else(|| anyhow!("{id} has no published versions"))?;
+        let dependencies = cargo::version_dependencies(id, &version.num)?
+            .iter()
+            .filter(|d| d.kind == "normal")
+            .count();
+        let dependents = cargo::reverse_dependency_count(id)?;
+        let features = version.features.keys().filter(|f| *f != "default").count();
+
+        let none = || "-".to_owned();
+        Ok(Self {
+            id: info.krate.id.to_owned(),
+            values: [
+                version.num.to_owned(),
+                // Only the date part of the timestamp
+                version.created_at.chars().take(10).collect(),
+                info.krate.downloads.to_string(),
+                info.krate.recent_downloads.to_string(),
+                version.license.to_owned().unwrap_or_else(none),
+                dependencies.to_string(),
+                dependents.to_string(),
+                version.rust_version.to_owned().unwrap_or_else(none),
+                features.to_string(),
+            ],
+        })
+    }
+}
+
+/// Marks or unmarks a crate for comparing, warns when too many are marked
+pub fn toggle_mark(app: &mut App, id: String) {
+    let marked = &mut app.compare_page.marked;
+    if let Some(i) = marked.iter().position(|m| *m == id) {
+        marked.remove(i);
+    } else if marked.len() < MAX_MARKED {
+        marked.push(id);
+    } else {
+        app.warn();
+    }
+}
+
+/// Opens the comparison and starts fetching, at least two crates need to be marked
+pub fn open(app: &mut App) {
+    let page = &mut app.compare_page;
+    if page.marked.len() < 2 {
+        app.warn();
+        return;
+    }
+    let request = Request::Compare(page.marked.to_owned());
+    page.open = true;
+    page.columns.clear();
+    app.worker.send(request.to_owned());
+    page.request = Some(request);
+}
+
+/// Fills in the columns, the comparison closes if it couldn't be fetched at all
+pub fn receive(app: &mut App, request: Request, response: Result<Response>) {
+    let page = &mut app.compare_page;
+    if page.request.as_ref() != Some(&request) {
+        return;
+    }
+    page.request = None;
+    match response {
+        Ok(Response::Compare(columns)) => page.columns = columns,
+        _ => page.open = false,
+    }
+    if !page.open || page.columns.iter().any(|c| c.is_err()) {
+        app.error();
+    }
+}
+
+/// The comparison is modal so it consumes every event while open
+pub fn event(app: &mut App, e: Event) {
+    if let Event::Key(key) = e {
+        if let Some(Action::Cancel | Action::Compare) =
+            app.config.keys.find(&key, keymap::COMPARE.actions)
+        {
+            app.compare_page.open = false;
+            if let Some(request) = app.compare_page.request.take() {
+                app.worker.cancel(&request);
+            }
+        }
+    }
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let page = &app.compare_page;
+    if !page.open {
+        return;
+    }
+    let theme = &app.theme;
+
+    let label_width = FIELDS.iter().map(|f| f.len()).max().unwrap_or(0) as u16 + 2;
+    let width = area
+        .width
+        .saturating_sub(8)
+        .min(label_width + 24 * MAX_MARKED as u16);
+    let area = centered_rect(area, width, FIELDS.len() as u16 + 5);
+    let block = Block::default()
+        .title(Span::styled(" compare ", theme.heading))
+        .borders(Borders::ALL)
+        .border_style(theme.border);
+    f.render_widget(Clear, area);
+
+    if page.request.is_some() {
+        let text = Span::styled(
+            format!("Fetching {}...", page.marked.join(", ")),
+            theme.dim_text,
+        );
+        f.render_widget(Paragraph::new(text).block(block), area);
+        return;
+    }
+
+    let header = Row::new(
+        std::iter::once(Cell::from(""))
+            .chain(page.columns.iter().zip(&page.marked).map(|(c, id)| {
+                let id = c.as_ref().map_or(id, |c| &c.id);
+                Cell::from(Span::styled(id.to_owned(), theme.heading))
+            }))
+            .collect::<Vec<_>>(),
+    )
+    .bottom_margin(1);
+
+    let rows = FIELDS.iter().enumerate().map(|(i, field)| {
+        let mut cells = vec![Cell::from(Span::styled(*field, theme.dim_text))];
+        for column in &page.columns {
+            cells.push(match column {
+                Ok(c) => Cell::from(Span::styled(c.values[i].to_owned(), theme.text)),
+                Err(_) => Cell::from(Span::styled("error", theme.error)),
+            });
+        }
+        Row::new(cells)
+    });
+
+    let column_count = page.columns.len().max(1) as u32;
+    let widths: Vec<Constraint> = std::iter::once(Constraint::Length(label_width))
+        .chain((0..column_count).map(|_| Constraint::Ratio(1, column_count)))
+        .collect();
+    f.render_widget(
+        Table::new(rows)
+            .header(header)
+            .block(block)
+            .widths(&widths)
+            .column_spacing(1),
+        area,
+    );
+}
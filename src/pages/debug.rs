@@ -0,0 +1,54 @@
+use crate::{app::App, cargo};
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const WIDTH: u16 = 28;
+
+/// Request counts for crates.io and background work, in the top right corner
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    if !app.debug_panel {
+        return;
+    }
+    let theme = &app.theme;
+    let stats = cargo::request_stats();
+    let rows = [
+        ("Requests", stats.requests.to_string()),
+        ("Retried", stats.retried.to_string()),
+        ("Rate limited", stats.rate_limited.to_string()),
+        ("Failed", stats.failed.to_string()),
+        ("Waited", format!("{:.1}s", stats.waited.as_secs_f32())),
+        ("Background jobs", app.worker.pending_count().to_string()),
+    ];
+    let lines: Vec<Spans> = rows
+        .into_iter()
+        .map(|(name, value)| {
+            Spans::from(vec![
+                Span::styled(format!("{name:<17}"), theme.dim_text),
+                Span::styled(value, theme.text),
+            ])
+        })
+        .collect();
+
+    let height = lines.len() as u16 + 2;
+    let area = Rect::new(
+        area.right().saturating_sub(WIDTH + 1),
+        area.y,
+        WIDTH.min(area.width),
+        height.min(area.height),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Text::from(lines)).block(
+            Block::default()
+                .title(Span::styled(" crates.io ", theme.heading))
+                .borders(Borders::ALL)
+                .border_style(theme.border),
+        ),
+        area,
+    );
+}
@@ -0,0 +1,156 @@
+use crate::{
+    app::App,
+    cargo::search::Crate,
+    keymap::{self, Action},
+    pages::search,
+    ui::centered_rect,
+};
+use crossterm::event::Event;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+#[derive(Default, Debug)]
+pub struct DetailsPage {
+    pub krate: Option<Crate>,
+}
+
+impl DetailsPage {
+    pub fn show(&mut self, krate: Crate) {
+        self.krate = Some(krate);
+    }
+    pub fn is_open(&self) -> bool {
+        self.krate.is_some()
+    }
+}
+
+/// The details are modal so they consume every event while open
+pub fn event(app: &mut App, e: Event) {
+    if let Event::Key(key) = e {
+        match app.config.keys.find(&key, keymap::DETAILS.actions) {
+            Some(Action::Cancel) => app.details_page.krate = None,
+            Some(action) => {
+                let c = app.details_page.krate.to_owned();
+                search::crate_action(app, action, c);
+            }
+            None => {}
+        }
+    }
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let Some(c) = &app.details_page.krate else {
+        return;
+    };
+    // Crates opened from outside search only have an id until their info arrives
+    if c.max_version.is_empty() {
+        if let Some(info) = app.info_cache.get(&c.id) {
+            app.details_page.krate = Some(info.krate.to_owned());
+        }
+    }
+    let Some(c) = &app.details_page.krate else {
+        return;
+    };
+    let theme = &app.theme;
+
+    let field = |name: &str, value: String| {
+        Spans::from(vec![
+            Span::styled(format!("{name:<18}"), theme.dim_text),
+            Span::styled(value, theme.text),
+        ])
+    };
+
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled(c.id.to_owned(), theme.heading),
+            Span::styled(format!(" {}", c.max_version), theme.dim_text),
+        ]),
+        Spans::from(Span::styled(
+            c.description
+                .to_owned()
+                .unwrap_or_else(|| "No description".into()),
+            theme.text,
+        )),
+        Spans::default(),
+        field(
+            "Stable version",
+            c.max_stable_version
+                .to_owned()
+                .unwrap_or_else(|| "-".into()),
+        ),
+        field("Downloads", c.downloads.to_string()),
+        field("Recent downloads", c.recent_downloads.to_string()),
+        field(
+            "Repository",
+            c.repository.to_owned().unwrap_or_else(|| "-".into()),
+        ),
+        match search::license(app, &c.id) {
+            Some(license) if !app.config.policy.allows(Some(license)) => Spans::from(vec![
+                Span::styled(format!("{:<18}", "License"), theme.dim_text),
+                Span::styled(format!("{license} (not allowed by policy)"), theme.warning),
+            ]),
+            Some(license) => field("License", license.to_owned()),
+            None => field("License", "...".into()),
+        },
+        match search::msrv(app, &c.id) {
+            Some((msrv, true)) => Spans::from(vec![
+                Span::styled(format!("{:<18}", "MSRV"), theme.dim_text),
+                Span::styled(msrv, theme.warning),
+            ]),
+            Some((msrv, false)) => field("MSRV", msrv),
+            None => field("MSRV", "-".into()),
+        },
+        Spans::default(),
+    ];
+
+    let version = c.max_stable_version.as_ref().unwrap_or(&c.max_version);
+    let affecting = app.advisories.affecting(&c.id, version);
+    let advisories = app.advisories.for_crate(&c.id);
+    if !advisories.is_empty() {
+        lines.push(Spans::from(Span::styled("Advisories", theme.heading)));
+        for advisory in advisories {
+            let style = match affecting.iter().any(|a| a.id == advisory.id) {
+                true if advisory.is_vulnerability() => theme.warning,
+                _ => theme.text,
+            };
+            lines.push(Spans::from(vec![
+                Span::styled(format!("{} ", advisory.id), style),
+                Span::styled(advisory.title.to_owned(), theme.text),
+                Span::styled(format!(" ({})", advisory.patched()), theme.dim_text),
+            ]));
+        }
+        lines.push(Spans::default());
+    }
+
+    let mut keys = vec![];
+    for action in keymap::DETAILS.actions {
+        if !keys.is_empty() {
+            keys.push(Span::styled(" • ", theme.dim_text));
+        }
+        keys.push(Span::styled(
+            format!("{} ", app.config.keys.get(*action).primary()),
+            theme.key,
+        ));
+        keys.push(Span::styled(action.description(), theme.dim_text));
+    }
+    lines.push(Spans::from(keys));
+
+    let width = area.width.saturating_sub(8).min(100);
+    let area = centered_rect(area, width, lines.len() as u16 + 4);
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .title(Span::styled(" details ", theme.heading))
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            ),
+        area,
+    );
+}
@@ -0,0 +1,171 @@
+use crate::{
+    app::App,
+    cargo::search::Crate,
+    keymap::{self, Action},
+    pages::{compare, search},
+    worker::Request,
+};
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use std::ops::Range;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    text::{Span, Spans, Text},
+    widgets::Paragraph,
+    Frame,
+};
+
+#[derive(Default, Debug)]
+pub struct FavouritesPage {
+    index: usize,
+    /// Where each favourite was last drawn, used for mouse hit testing
+    rects: Vec<Rect>,
+    /// The favourites on screen when last drawn
+    visible: Range<usize>,
+}
+
+/// The favourites on screen, their info is loaded in the background
+pub fn visible_ids(app: &App) -> Vec<String> {
+    let crates = &app.config.favourites.crates;
+    let visible = &app.favourites_page.visible;
+    crates
+        .get(visible.start.min(crates.len())..visible.end.min(crates.len()))
+        .unwrap_or_default()
+        .to_vec()
+}
+
+fn selected(app: &App) -> Option<String> {
+    app.config
+        .favourites
+        .crates
+        .get(app.favourites_page.index)
+        .cloned()
+}
+
+pub fn event(app: &mut App, e: Event) -> bool {
+    let count = app.config.favourites.crates.len();
+    match e {
+        Event::Key(key) => match app.config.keys.find(&key, keymap::FAVOURITES.actions) {
+            Some(Action::Up) => {
+                app.favourites_page.index = app.favourites_page.index.saturating_sub(1)
+            }
+            Some(Action::Down) => {
+                app.favourites_page.index = (app.favourites_page.index + 1).min(count.max(1) - 1)
+            }
+            Some(Action::Mark) => match selected(app) {
+                Some(id) => compare::toggle_mark(app, id),
+                None => app.warn(),
+            },
+            Some(Action::Compare) => compare::open(app),
+            Some(action) => {
+                // Only the id is known until the crate's info arrives, which is all these actions need
+                let c = selected(app).map(|id| match app.info_cache.get(&id) {
+                    Some(info) => info.krate.to_owned(),
+                    None => Crate {
+                        id,
+                        ..Default::default()
+                    },
+                });
+                search::crate_action(app, action, c);
+            }
+            None => return true,
+        },
+        Event::Mouse(e) if app.config.mouse.enabled => {
+            let clicked = Rect::new(e.column, e.row, 1, 1);
+            let Some(index) = app
+                .favourites_page
+                .rects
+                .iter()
+                .position(|r| r.intersects(clicked))
+            else {
+                return true;
+            };
+            match e.kind {
+                MouseEventKind::Moved if app.favourites_page.index == index => return true,
+                MouseEventKind::Moved | MouseEventKind::Down(MouseButton::Left) => {
+                    app.favourites_page.index = index
+                }
+                _ => return true,
+            }
+        }
+        _ => return true,
+    }
+    false
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let chunks = Layout::default()
+        .vertical_margin(1)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(area);
+    let crates = &app.config.favourites.crates;
+    let page = &mut app.favourites_page;
+    page.index = page.index.min(crates.len().saturating_sub(1));
+
+    // Keep the selection on screen
+    let height = chunks[0].height as usize;
+    let skip = (page.index + 1).saturating_sub(height);
+    page.visible = skip..skip + height;
+    let theme = &app.theme;
+
+    let mut lines = vec![];
+    page.rects.clear();
+    for (i, id) in crates.iter().enumerate().skip(skip).take(height) {
+        let selected = i == page.index;
+        let style = if selected {
+            theme.heading.patch(theme.selection)
+        } else {
+            theme.heading
+        };
+        let prefix = if selected { " │ " } else { "   " };
+        let mark = if app.compare_page.is_marked(id) {
+            " ✓"
+        } else {
+            ""
+        };
+        let summary = match app.info_cache.get(id) {
+            Some(info) => {
+                let version = info.latest().map(|v| v.num.as_str()).unwrap_or_default();
+                let description = info.krate.description.as_deref().unwrap_or_default();
+                format!("  {version}  {}", description.trim())
+            }
+            None if app.worker.is_pending(&Request::Info(id.to_owned())) => "  loading...".into(),
+            None => String::new(),
+        };
+        lines.push(Spans::from(vec![
+            Span::styled(prefix, style),
+            Span::styled(id.to_owned(), style),
+            Span::styled(mark, theme.dim_text),
+            Span::styled(summary, theme.dim_text),
+        ]));
+        page.rects.push(Rect::new(
+            chunks[0].x,
+            chunks[0].y + lines.len() as u16 - 1,
+            chunks[0].width,
+            1,
+        ));
+    }
+    if crates.is_empty() {
+        lines.push(Spans::from(Span::styled(
+            format!(
+                "   No favourites yet, press {} on a search result to add one",
+                app.config.keys.get(Action::Favourite).primary()
+            ),
+            theme.dim_text,
+        )));
+    }
+    f.render_widget(Paragraph::new(Text::from(lines)), chunks[0]);
+
+    let mut keys = vec![Span::raw("   ")];
+    for action in keymap::FAVOURITES.actions {
+        if keys.len() > 1 {
+            keys.push(Span::styled(" • ", theme.dim_text));
+        }
+        keys.push(Span::styled(
+            format!("{} ", app.config.keys.get(*action).primary()),
+            theme.key,
+        ));
+        keys.push(Span::styled(action.description(), theme.dim_text));
+    }
+    f.render_widget(Paragraph::new(Spans::from(keys)), chunks[1]);
+}
@@ -0,0 +1,83 @@
+use crate::{
+    app::App,
+    keymap::{self, Action},
+    ui::centered_rect,
+};
+use crossterm::event::Event;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+#[derive(Default, Debug)]
+pub struct HelpPage {
+    pub open: bool,
+    scroll: u16,
+}
+
+/// The help menu is modal so it consumes every event while open
+pub fn event(app: &mut App, e: Event) {
+    if let Event::Key(key) = e {
+        match app.config.keys.find(&key, keymap::HELP.actions) {
+            Some(Action::Up) => app.help_page.scroll = app.help_page.scroll.saturating_sub(1),
+            Some(Action::Down) => app.help_page.scroll += 1,
+            Some(Action::Help) | Some(Action::Cancel) => {
+                app.help_page.open = false;
+                app.help_page.scroll = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    let keys = &app.config.keys;
+
+    let key_width = keymap::CONTEXTS
+        .iter()
+        .flat_map(|context| context.actions)
+        .map(|action| keys.get(*action).to_string().len())
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    let mut lines = vec![];
+    for context in keymap::CONTEXTS {
+        if !lines.is_empty() {
+            lines.push(Spans::default());
+        }
+        lines.push(Spans::from(Span::styled(context.name, theme.heading)));
+        for action in context.actions {
+            lines.push(Spans::from(vec![
+                Span::styled(
+                    format!("  {:key_width$}", keys.get(*action).to_string()),
+                    theme.key,
+                ),
+                Span::styled(action.description(), theme.dim_text),
+            ]));
+        }
+    }
+
+    // Keep the last line on screen when scrolling
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(area, key_width as u16 + 24, height);
+    let scroll = app.help_page.scroll.min(height.saturating_sub(area.height));
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Text::from(lines))
+            .block(
+                Block::default()
+                    .title(Span::styled(" help ", theme.heading))
+                    .borders(Borders::ALL)
+                    .border_style(theme.border),
+            )
+            .scroll((scroll, 0)),
+        area,
+    );
+    app.help_page.scroll = scroll;
+}
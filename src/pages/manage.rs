@@ -1,14 +1,824 @@
-use crate::app::App;
-use crossterm::event::Event;
-use tui::{backend::Backend, layout::Rect, Frame};
+use crate::{
+    app::App,
+    cargo::{self, search::Crate, Duplicate, Metadata, ProjectDependency},
+    keymap::{self, Action},
+    pages::search,
+    worker::{Request, Response, Worker},
+};
+use anyhow::Result;
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    time::SystemTime,
+};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    text::{Span, Spans, Text},
+    widgets::Paragraph,
+    Frame,
+};
 
 #[derive(Default, Debug)]
 pub struct ManagePage {
-    _expanded_help_message: bool,
+    index: usize,
+    dependencies: Vec<ProjectDependency>,
+    /// Dependencies the project's code never refers to
+    unused: Vec<String>,
+    /// Modification times of Cargo.toml and Cargo.lock when they were last read
+    modified: Option<[Option<SystemTime>; 2]>,
+    /// Whether the tab was open the last time it was polled
+    open: bool,
+    /// Where each dependency was last drawn, used for mouse hit testing
+    rects: Vec<Rect>,
+    view: View,
+    /// Every package in the project's dependency graph, for the license report
+    metadata: Option<Result<Metadata>>,
+    scroll: u16,
+    /// Tree nodes as they were last drawn, for selecting and expanding
+    tree: Vec<TreeRow>,
+    tree_index: usize,
+    /// Paths of package ids from a root to each expanded node
+    expanded: HashSet<Vec<String>>,
+    duplicates: Option<Result<Vec<Duplicate>>>,
+    duplicate_index: usize,
 }
 
-pub fn event(_app: &mut App, _e: Event) -> bool {
-    true
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    #[default]
+    Dependencies,
+    Licenses,
+    Tree,
+    Duplicates,
 }
 
-pub fn update<B: Backend>(_f: &mut Frame<B>, _app: &mut App, _area: Rect) {}
+#[derive(Debug)]
+struct TreeRow {
+    path: Vec<String>,
+    name: String,
+    /// `dev` or `build` for dependencies only used as those
+    kind: Option<String>,
+    has_children: bool,
+}
+
+impl ManagePage {
+    /// The packages of the manifest's dependencies
+    pub fn dependency_ids(&self) -> Vec<String> {
+        self.dependencies
+            .iter()
+            .map(|d| d.package.to_owned())
+            .collect()
+    }
+    /// Re-reads the dependencies when the manifest or lockfile has changed,
+    /// returns whether they were
+    fn reload(&mut self, worker: &mut Worker) -> bool {
+        let modified = ["Cargo.toml", "Cargo.lock"]
+            .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok());
+        if self.modified == Some(modified) {
+            return false;
+        }
+        self.modified = Some(modified);
+        // Anything still loading was read from the old files
+        worker.cancel(&Request::Metadata);
+        worker.cancel(&Request::Duplicates);
+        self.metadata = None;
+        self.duplicates = None;
+        self.dependencies = cargo::project_dependencies().unwrap_or_default();
+        self.index = self.index.min(self.dependencies.len().saturating_sub(1));
+        true
+    }
+    fn selected(&self) -> Option<&ProjectDependency> {
+        self.dependencies.get(self.index)
+    }
+    /// The name of the selected crate in whichever view is open
+    fn selected_name(&self) -> Option<&str> {
+        match self.view {
+            View::Tree => self.tree.get(self.tree_index).map(|row| row.name.as_str()),
+            View::Duplicates => self.selected_duplicate().map(|d| d.name.as_str()),
+            _ => self.selected().map(|dep| dep.package.as_str()),
+        }
+    }
+    fn selected_duplicate(&self) -> Option<&Duplicate> {
+        match &self.duplicates {
+            Some(Ok(duplicates)) => duplicates.get(self.duplicate_index),
+            _ => None,
+        }
+    }
+}
+
+/// Picks up changes to the manifest while the tab is open, runs `cargo metadata`
+/// for the views that need it and finds duplicates, which hits crates.io, only
+/// once they're opened. Returns whether the dependencies were reloaded
+pub fn poll(app: &mut App) -> bool {
+    let open = app.tab == 1;
+    let opened = open && !app.manage_page.open;
+    app.manage_page.open = open;
+    let reloaded = open && app.manage_page.reload(&mut app.worker);
+    // Code can change without touching the manifest, so it's scanned again
+    // whenever the tab is opened
+    if opened || reloaded {
+        app.worker.cancel(&Request::Unused);
+        app.worker.send(Request::Unused);
+    }
+    let page = &app.manage_page;
+    match page.view {
+        View::Licenses | View::Tree if page.metadata.is_none() => {
+            app.worker.send(Request::Metadata)
+        }
+        View::Duplicates if page.duplicates.is_none() => app.worker.send(Request::Duplicates),
+        _ => {}
+    }
+    reloaded
+}
+
+pub fn receive(app: &mut App, request: Request, response: Result<Response>) {
+    let page = &mut app.manage_page;
+    match (request, response) {
+        (Request::Metadata, Ok(Response::Metadata(metadata))) => page.metadata = Some(Ok(metadata)),
+        (Request::Metadata, Err(e)) => page.metadata = Some(Err(e)),
+        (Request::Duplicates, Ok(Response::Duplicates(duplicates))) => {
+            page.duplicates = Some(Ok(duplicates))
+        }
+        (Request::Duplicates, Err(e)) => page.duplicates = Some(Err(e)),
+        (Request::Unused, response) => {
+            page.unused = match response {
+                Ok(Response::Unused(unused)) => unused,
+                _ => vec![],
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn event(app: &mut App, e: Event) -> bool {
+    let page = &mut app.manage_page;
+    match e {
+        Event::Key(key) => match app.config.keys.find(&key, keymap::MANAGE.actions) {
+            Some(action @ (Action::Licenses | Action::Tree | Action::Duplicates)) => {
+                let view = match action {
+                    Action::Licenses => View::Licenses,
+                    Action::Tree => View::Tree,
+                    _ => View::Duplicates,
+                };
+                page.scroll = 0;
+                page.view = match page.view == view {
+                    true => View::Dependencies,
+                    false => view,
+                };
+            }
+            // The report scrolls instead of selecting
+            Some(Action::Up) if page.view == View::Licenses => {
+                page.scroll = page.scroll.saturating_sub(1)
+            }
+            Some(Action::Down) if page.view == View::Licenses => page.scroll += 1,
+            Some(Action::Up) if page.view == View::Tree => {
+                page.tree_index = page.tree_index.saturating_sub(1)
+            }
+            Some(Action::Down) if page.view == View::Tree => {
+                page.tree_index = (page.tree_index + 1).min(page.tree.len().saturating_sub(1))
+            }
+            Some(Action::Up) if page.view == View::Duplicates => {
+                page.duplicate_index = page.duplicate_index.saturating_sub(1)
+            }
+            Some(Action::Down) if page.view == View::Duplicates => {
+                let count = match &page.duplicates {
+                    Some(Ok(duplicates)) => duplicates.len(),
+                    _ => 0,
+                };
+                page.duplicate_index = (page.duplicate_index + 1).min(count.saturating_sub(1))
+            }
+            Some(Action::Up) => page.index = page.index.saturating_sub(1),
+            Some(Action::Down) => {
+                page.index = (page.index + 1).min(page.dependencies.len().saturating_sub(1))
+            }
+            Some(Action::Expand) => match page.tree.get(page.tree_index) {
+                Some(row) if page.view == View::Tree && row.has_children => {
+                    page.expanded.insert(row.path.to_owned());
+                }
+                _ => app.warn(),
+            },
+            // Collapsing a node that isn't expanded moves up to its parent
+            Some(Action::Collapse) => match page.tree.get(page.tree_index) {
+                Some(row) if page.view == View::Tree => {
+                    if !page.expanded.remove(&row.path) {
+                        let parent = &row.path[..row.path.len() - 1];
+                        if let Some(i) = page.tree.iter().position(|r| r.path == parent) {
+                            page.tree_index = i;
+                        }
+                    }
+                }
+                _ => app.warn(),
+            },
+            Some(Action::Bump) => {
+                let bumps = match page.selected_duplicate().map(|d| &d.bumps) {
+                    Some(Ok(bumps)) if page.view == View::Duplicates => bumps.to_owned(),
+                    _ => vec![],
+                };
+                match bumps.is_empty() {
+                    true => app.warn(),
+                    false => {
+                        app.preview_edit(cargo::bump(&bumps), vec![]);
+                    }
+                }
+            }
+            Some(Action::Details) => match page.selected_name() {
+                Some(name) => {
+                    let c = app
+                        .info_cache
+                        .get(name)
+                        .map(|info| info.krate.to_owned())
+                        .unwrap_or_else(|| Crate {
+                            id: name.to_owned(),
+                            ..Default::default()
+                        });
+                    app.details_page.show(c);
+                }
+                None => app.warn(),
+            },
+            // Removes by the manifest name, which differs from the crate when renamed
+            Some(Action::Remove) => match page.selected().map(|dep| dep.name.to_owned()) {
+                Some(name) if page.view == View::Dependencies => {
+                    app.preview_edit(cargo::remove(name), vec![]);
+                }
+                _ => app.warn(),
+            },
+            Some(action) => {
+                let c = page.selected_name().map(|name| Crate {
+                    id: name.to_owned(),
+                    ..Default::default()
+                });
+                search::crate_action(app, action, c);
+            }
+            None => return true,
+        },
+        Event::Mouse(e) if app.config.mouse.enabled => {
+            let clicked = Rect::new(e.column, e.row, 1, 1);
+            let Some(index) = page.rects.iter().position(|r| r.intersects(clicked)) else {
+                return true;
+            };
+            match e.kind {
+                MouseEventKind::Moved | MouseEventKind::Down(MouseButton::Left) => {
+                    let selected = match page.view {
+                        View::Tree => &mut page.tree_index,
+                        View::Duplicates => &mut page.duplicate_index,
+                        _ => &mut page.index,
+                    };
+                    // Moving within the hovered row changes nothing
+                    if e.kind == MouseEventKind::Moved && *selected == index {
+                        return true;
+                    }
+                    *selected = index;
+                }
+                _ => return true,
+            }
+        }
+        _ => return true,
+    }
+    false
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let chunks = Layout::default()
+        .vertical_margin(1)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(area);
+    let status = match app.manage_page.view {
+        View::Dependencies => draw_dependencies(f, app, chunks[0]),
+        View::Licenses => draw_licenses(f, app, chunks[0]),
+        View::Tree => draw_tree(f, app, chunks[0]),
+        View::Duplicates => draw_duplicates(f, app, chunks[0]),
+    };
+    f.render_widget(Paragraph::new(status), chunks[1]);
+
+    let theme = &app.theme;
+    let mut keys = vec![Span::raw("   ")];
+    let view = app.manage_page.view;
+    let actions = keymap::MANAGE.actions.iter().filter(|action| match action {
+        Action::Expand | Action::Collapse => view == View::Tree,
+        Action::Remove => view == View::Dependencies,
+        Action::Bump => view == View::Duplicates,
+        _ => true,
+    });
+    for action in actions {
+        if keys.len() > 1 {
+            keys.push(Span::styled(" • ", theme.dim_text));
+        }
+        keys.push(Span::styled(
+            format!("{} ", app.config.keys.get(*action).primary()),
+            theme.key,
+        ));
+        keys.push(Span::styled(action.description(), theme.dim_text));
+    }
+    f.render_widget(Paragraph::new(Spans::from(keys)), chunks[2]);
+}
+
+/// Draws the manifest's dependencies and returns the status line
+fn draw_dependencies<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Span<'static> {
+    let theme = &app.theme;
+    let page = &mut app.manage_page;
+
+    let name_width = page
+        .dependencies
+        .iter()
+        .map(|d| d.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let req_width = page
+        .dependencies
+        .iter()
+        .map(|d| d.req.as_deref().unwrap_or("-").chars().count())
+        .max()
+        .unwrap_or(0);
+
+    // Keep the selection on screen
+    let height = area.height as usize;
+    let skip = (page.index + 1).saturating_sub(height);
+
+    let mut lines = vec![];
+    let mut affected = 0;
+    let mut yanked = 0;
+    let unused = page.unused.len();
+    page.rects.clear();
+    for (i, dep) in page.dependencies.iter().enumerate() {
+        let version = dep.locked.as_deref().or(dep.req.as_deref()).unwrap_or("");
+        let advisories = app.advisories.affecting(&dep.package, version);
+        if !advisories.is_empty() {
+            affected += 1;
+        }
+        let info = app.info_cache.get(&dep.package);
+        let locked_yanked = info
+            .zip(dep.locked.as_deref())
+            .is_some_and(|(info, locked)| info.is_yanked(locked));
+        let req_yanked = info
+            .zip(dep.req.as_deref())
+            .is_some_and(|(info, req)| info.is_req_yanked(req));
+        if locked_yanked || req_yanked {
+            yanked += 1;
+        }
+        if i < skip || i >= skip + height {
+            page.rects.push(Rect::default());
+            continue;
+        }
+
+        let selected = i == page.index;
+        let with_selection = |style: Style| {
+            if selected {
+                style.patch(theme.selection)
+            } else {
+                style
+            }
+        };
+        let mut spans = vec![
+            Span::styled(
+                format!(
+                    "{}{:name_width$}  ",
+                    if selected { " │ " } else { "   " },
+                    dep.name
+                ),
+                with_selection(theme.heading),
+            ),
+            Span::styled(
+                format!("{:req_width$}  ", dep.req.as_deref().unwrap_or("-")),
+                with_selection(theme.text),
+            ),
+            Span::styled(
+                dep.locked
+                    .as_ref()
+                    .map(|v| format!("locked {v}  "))
+                    .unwrap_or_default(),
+                with_selection(theme.dim_text),
+            ),
+        ];
+        if page.unused.contains(&dep.name) {
+            spans.push(Span::styled("unused  ", with_selection(theme.warning)));
+        }
+        if req_yanked {
+            spans.push(Span::styled(
+                "⚠ requirement yanked  ",
+                with_selection(theme.warning),
+            ));
+        } else if locked_yanked {
+            spans.push(Span::styled(
+                "⚠ locked version yanked  ",
+                with_selection(theme.warning),
+            ));
+        }
+        for advisory in advisories {
+            let (text, style) = match &advisory.informational {
+                Some(kind) => (format!("{} {kind}  ", advisory.id), theme.dim_text),
+                None => (format!("⚠ {}  ", advisory.id), theme.warning),
+            };
+            spans.push(Span::styled(text, with_selection(style)));
+        }
+        lines.push(Spans::from(spans));
+        page.rects.push(Rect::new(
+            area.x,
+            area.y + lines.len() as u16 - 1,
+            area.width,
+            1,
+        ));
+    }
+    if page.dependencies.is_empty() {
+        lines.push(Spans::from(Span::styled(
+            "   No dependencies found in ./Cargo.toml",
+            theme.dim_text,
+        )));
+    }
+    f.render_widget(Paragraph::new(Text::from(lines)), area);
+
+    let mut problems = vec![];
+    if affected > 0 {
+        problems.push(format!("{affected} dependencies have advisories"));
+    }
+    if yanked > 0 {
+        problems.push(format!("{yanked} use yanked versions"));
+    }
+    if unused > 0 {
+        let remove = app.config.keys.get(Action::Remove).primary();
+        problems.push(format!(
+            "{unused} look unused ({remove} removes the selected one)"
+        ));
+    }
+    if !problems.is_empty() {
+        Span::styled(format!("   {}", problems.join(", ")), theme.warning)
+    } else if app.advisories.is_empty() {
+        Span::styled(
+            "   No advisory database loaded, run :advisories to download it",
+            theme.dim_text,
+        )
+    } else {
+        Span::styled("   No known advisories", theme.dim_text)
+    }
+}
+
+/// Draws every package's license grouped by license and returns the status line
+fn draw_licenses<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Span<'static> {
+    let theme = &app.theme;
+    let page = &mut app.manage_page;
+    let metadata = match &page.metadata {
+        Some(Ok(metadata)) => metadata,
+        Some(Err(e)) => return Span::styled(format!("   {e}"), theme.error),
+        None => return Span::styled("   Running cargo metadata...", theme.dim_text),
+    };
+
+    let mut licenses: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for package in metadata.dependencies() {
+        licenses
+            .entry(package.license.as_deref().unwrap_or("unknown"))
+            .or_default()
+            .push(&package.name);
+    }
+    let mut licenses: Vec<_> = licenses.into_iter().collect();
+    licenses.sort_by_key(|(_, names)| std::cmp::Reverse(names.len()));
+
+    let policy = &app.config.policy;
+    let mut disallowed = 0;
+    let mut lines = vec![];
+    for (license, names) in licenses {
+        let allowed = policy.allows(Some(license).filter(|l| *l != "unknown"));
+        if !allowed {
+            disallowed += names.len();
+        }
+        lines.push(Spans::from(vec![
+            Span::styled(format!("   {:>4}  ", names.len()), theme.dim_text),
+            Span::styled(
+                license.to_owned(),
+                if allowed {
+                    theme.heading
+                } else {
+                    theme.warning
+                },
+            ),
+        ]));
+
+        // Names are wrapped by hand to keep them indented under the license
+        let mut names = names;
+        names.sort_unstable();
+        names.dedup();
+        let mut line = String::new();
+        for name in names {
+            if !line.is_empty() && 9 + line.len() + name.len() + 2 > area.width as usize {
+                lines.push(Spans::from(Span::styled(
+                    format!("         {line}"),
+                    theme.dim_text,
+                )));
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push_str(", ");
+            }
+            line.push_str(name);
+        }
+        lines.push(Spans::from(Span::styled(
+            format!("         {line}"),
+            theme.dim_text,
+        )));
+    }
+
+    let height = lines.len() as u16;
+    page.scroll = page.scroll.min(height.saturating_sub(area.height));
+    f.render_widget(
+        Paragraph::new(Text::from(lines)).scroll((page.scroll, 0)),
+        area,
+    );
+
+    let count = metadata.dependencies().count();
+    match (policy.is_enabled(), disallowed) {
+        (false, _) => Span::styled(
+            format!("   {count} packages, set allowed_licenses in the config to check them"),
+            theme.dim_text,
+        ),
+        (true, 0) => Span::styled(
+            format!("   All {count} packages have allowed licenses"),
+            theme.dim_text,
+        ),
+        (true, n) => Span::styled(
+            format!("   {n} of {count} packages have licenses the policy doesn't allow"),
+            theme.warning,
+        ),
+    }
+}
+
+/// Draws the project's dependency graph as a tree and returns the status line
+fn draw_tree<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Span<'static> {
+    let theme = &app.theme;
+    let page = &mut app.manage_page;
+    let ManagePage {
+        metadata,
+        tree,
+        tree_index,
+        expanded,
+        rects,
+        ..
+    } = page;
+    let metadata = match metadata {
+        Some(Ok(metadata)) => metadata,
+        Some(Err(e)) => return Span::styled(format!("   {e}"), theme.error),
+        None => return Span::styled("   Running cargo metadata...", theme.dim_text),
+    };
+
+    // The project's own packages start expanded
+    let roots = metadata.roots();
+    if tree.is_empty() {
+        expanded.extend(roots.iter().map(|root| vec![root.to_string()]));
+    }
+    tree.clear();
+    for root in roots {
+        tree_rows(metadata, expanded, vec![root.to_owned()], None, tree);
+    }
+    *tree_index = (*tree_index).min(tree.len().saturating_sub(1));
+
+    // Keep the selection on screen
+    let height = area.height as usize;
+    let skip = (*tree_index + 1).saturating_sub(height);
+
+    let mut lines = vec![];
+    rects.clear();
+    for (i, row) in tree.iter().enumerate() {
+        // Hidden rows still get a rect so clicks map to the right index
+        if i < skip || i >= skip + height {
+            rects.push(Rect::default());
+            continue;
+        }
+        let id = row.path.last().map(|id| id.as_str()).unwrap_or_default();
+        let selected = i == *tree_index;
+        let with_selection = |style: Style| match selected {
+            true => style.patch(theme.selection),
+            false => style,
+        };
+        let marker = match (row.has_children, expanded.contains(&row.path)) {
+            (false, _) => "  ",
+            (true, false) => "▸ ",
+            (true, true) => "▾ ",
+        };
+        let mut spans = vec![
+            Span::styled(
+                format!(
+                    "{}{}{marker}",
+                    if selected { " │ " } else { "   " },
+                    "  ".repeat(row.path.len() - 1),
+                ),
+                with_selection(theme.dim_text),
+            ),
+            Span::styled(row.name.to_owned(), with_selection(theme.heading)),
+        ];
+        if let Some(package) = metadata.package(id) {
+            spans.push(Span::styled(
+                format!(" {}", package.version),
+                with_selection(theme.text),
+            ));
+        }
+        if let Some(kind) = &row.kind {
+            spans.push(Span::styled(
+                format!(" ({kind})"),
+                with_selection(theme.dim_text),
+            ));
+        }
+        if metadata.is_duplicated(&row.name) {
+            spans.push(Span::styled(" duplicated", with_selection(theme.warning)));
+        }
+        if let Some(node) = metadata.node(id).filter(|n| !n.features.is_empty()) {
+            spans.push(Span::styled(
+                format!("  {}", node.features.join(", ")),
+                with_selection(theme.dim_text),
+            ));
+        }
+        lines.push(Spans::from(spans));
+        rects.push(Rect::new(
+            area.x,
+            area.y + lines.len() as u16 - 1,
+            area.width,
+            1,
+        ));
+    }
+    f.render_widget(Paragraph::new(Text::from(lines)), area);
+
+    let count = metadata.dependencies().count();
+    let mut duplicated: Vec<_> = metadata
+        .dependencies()
+        .map(|p| p.name.as_str())
+        .filter(|name| metadata.is_duplicated(name))
+        .collect();
+    duplicated.sort_unstable();
+    duplicated.dedup();
+    match duplicated.len() {
+        0 => Span::styled(format!("   {count} packages"), theme.dim_text),
+        n => Span::styled(
+            format!("   {count} packages, {n} with more than one version"),
+            theme.warning,
+        ),
+    }
+}
+
+/// Adds a node and, when it's expanded, everything below it
+fn tree_rows(
+    metadata: &Metadata,
+    expanded: &HashSet<Vec<String>>,
+    path: Vec<String>,
+    kind: Option<String>,
+    rows: &mut Vec<TreeRow>,
+) {
+    let id = path.last().map(|id| id.as_str()).unwrap_or_default();
+    let name = metadata
+        .package(id)
+        .map(|p| p.name.to_owned())
+        .unwrap_or_else(|| id.to_owned());
+    // Stops at cycles, which dev-dependencies can create
+    let deps = match path[..path.len() - 1].iter().any(|p| p == id) {
+        true => &[][..],
+        false => metadata
+            .node(id)
+            .map(|n| n.deps.as_slice())
+            .unwrap_or_default(),
+    };
+    let is_expanded = expanded.contains(&path);
+    rows.push(TreeRow {
+        path: path.to_owned(),
+        name,
+        kind,
+        has_children: !deps.is_empty(),
+    });
+    if !is_expanded {
+        return;
+    }
+    let mut deps: Vec<_> = deps.iter().collect();
+    deps.sort_by_key(|dep| metadata.package(&dep.pkg).map(|p| p.name.as_str()));
+    for dep in deps {
+        let mut path = path.to_owned();
+        path.push(dep.pkg.to_owned());
+        tree_rows(metadata, expanded, path, dep.kind().map(String::from), rows);
+    }
+}
+
+/// Draws crates locked at more than one version and returns the status line
+fn draw_duplicates<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Span<'static> {
+    let theme = &app.theme;
+    let page = &mut app.manage_page;
+    let duplicates = match &page.duplicates {
+        Some(Ok(duplicates)) => duplicates,
+        Some(Err(e)) => return Span::styled(format!("   {e}"), theme.error),
+        None => {
+            return Span::styled(
+                "   Checking Cargo.lock and crates.io for duplicates...",
+                theme.dim_text,
+            )
+        }
+    };
+    page.duplicate_index = page.duplicate_index.min(duplicates.len().saturating_sub(1));
+
+    let name_width = duplicates
+        .iter()
+        .map(|d| d.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let version_width = duplicates
+        .iter()
+        .flat_map(|d| &d.versions)
+        .map(|v| v.version.chars().count())
+        .max()
+        .unwrap_or(0);
+    let indent = " ".repeat(name_width + 5);
+
+    let mut lines = vec![];
+    let mut selected_lines = 0..0;
+    page.rects.clear();
+    for (i, duplicate) in duplicates.iter().enumerate() {
+        let start = lines.len();
+        let selected = i == page.duplicate_index;
+        let with_selection = |style: Style| match selected {
+            true => style.patch(theme.selection),
+            false => style,
+        };
+        for (j, version) in duplicate.versions.iter().enumerate() {
+            let name = match j {
+                0 => format!(
+                    "{}{:name_width$}  ",
+                    if selected { " │ " } else { "   " },
+                    duplicate.name
+                ),
+                _ => indent.to_owned(),
+            };
+            lines.push(Spans::from(vec![
+                Span::styled(name, with_selection(theme.heading)),
+                Span::styled(
+                    format!("{:version_width$}  ", version.version),
+                    with_selection(theme.text),
+                ),
+                Span::styled(
+                    version.dependents.join(", "),
+                    with_selection(theme.dim_text),
+                ),
+            ]));
+        }
+        let (text, style) = match &duplicate.bumps {
+            Ok(bumps) if bumps.is_empty() => (
+                "No bump of a direct dependency removes it".to_owned(),
+                theme.dim_text,
+            ),
+            Ok(bumps) => (
+                bumps
+                    .iter()
+                    .map(|b| {
+                        let note = if b.certain { "" } else { " (check first)" };
+                        format!("Bump {} {} → {}{note}", b.name, b.from, b.to)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                theme.key,
+            ),
+            Err(e) => (format!("Unable to check crates.io: {e}"), theme.error),
+        };
+        lines.push(Spans::from(vec![
+            Span::styled(indent.to_owned(), with_selection(theme.text)),
+            Span::styled(text, with_selection(style)),
+        ]));
+        lines.push(Spans::default());
+        if selected {
+            selected_lines = start..lines.len();
+        }
+        page.rects.push(Rect::new(
+            area.x,
+            area.y + start as u16,
+            area.width,
+            (lines.len() - start) as u16,
+        ));
+    }
+
+    // Keep the selected duplicate on screen
+    let height = area.height as usize;
+    let scroll = selected_lines
+        .end
+        .saturating_sub(height)
+        .min(selected_lines.start);
+    for rect in &mut page.rects {
+        match rect.y.checked_sub(scroll as u16) {
+            Some(y) if y >= area.y && y < area.y + area.height => {
+                rect.y = y;
+                rect.height = rect.height.min(area.y + area.height - y);
+            }
+            _ => *rect = Rect::default(),
+        }
+    }
+    f.render_widget(
+        Paragraph::new(Text::from(lines)).scroll((scroll as u16, 0)),
+        area,
+    );
+
+    match duplicates.len() {
+        0 => Span::styled(
+            "   No crates are locked at more than one version",
+            theme.dim_text,
+        ),
+        n => Span::styled(
+            format!("   {n} crates are locked at more than one version"),
+            theme.warning,
+        ),
+    }
+}
@@ -1,2 +1,10 @@
+pub mod command_line;
+pub mod compare;
+pub mod debug;
+pub mod details;
+pub mod favourites;
+pub mod help;
 pub mod manage;
+pub mod preview;
 pub mod search;
+pub mod versions;
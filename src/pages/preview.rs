@@ -0,0 +1,127 @@
+use crate::{
+    app::App,
+    cargo::ManifestEdit,
+    keymap::{self, Action},
+    ui::centered_rect,
+};
+use crossterm::event::Event;
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Layout, Rect},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Shows a manifest change as a diff before it's written
+#[derive(Default, Debug)]
+pub struct EditPreview {
+    edit: Option<ManifestEdit>,
+    /// Advisories and license policy problems with what's being added
+    warnings: Vec<String>,
+    scroll: u16,
+}
+
+impl EditPreview {
+    pub fn open(&mut self, edit: ManifestEdit, warnings: Vec<String>) {
+        *self = Self {
+            edit: Some(edit),
+            warnings,
+            scroll: 0,
+        };
+    }
+    pub fn is_open(&self) -> bool {
+        self.edit.is_some()
+    }
+}
+
+/// The preview is modal so it consumes every event while open
+pub fn event(app: &mut App, e: Event) {
+    let Event::Key(key) = e else {
+        return;
+    };
+    let preview = &mut app.edit_preview;
+    match app.config.keys.find(&key, keymap::PREVIEW.actions) {
+        Some(Action::Up) => preview.scroll = preview.scroll.saturating_sub(1),
+        Some(Action::Down) => preview.scroll += 1,
+        Some(Action::Submit) => {
+            if let Some(edit) = preview.edit.take() {
+                app.apply_edit(edit);
+            }
+        }
+        Some(Action::Cancel) => preview.edit = None,
+        _ => {}
+    }
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    let preview = &mut app.edit_preview;
+    let Some(edit) = &preview.edit else {
+        return;
+    };
+
+    let mut lines: Vec<Spans> = preview
+        .warnings
+        .iter()
+        .map(|w| Spans::from(Span::styled(format!("⚠ {w}"), theme.warning)))
+        .collect();
+    if !lines.is_empty() {
+        lines.push(Spans::default());
+    }
+    for line in edit.diff() {
+        let style = match line.chars().next() {
+            _ if line.starts_with("---") || line.starts_with("+++") => theme.heading,
+            Some('@') => theme.dim_text,
+            Some('+') => theme.key,
+            Some('-') => theme.error,
+            _ => theme.text,
+        };
+        lines.push(Spans::from(Span::styled(line, style)));
+    }
+
+    let mut keys = vec![];
+    for action in [Action::Submit, Action::Cancel] {
+        if !keys.is_empty() {
+            keys.push(Span::styled(" • ", theme.dim_text));
+        }
+        keys.push(Span::styled(
+            format!("{} ", app.config.keys.get(action).primary()),
+            theme.key,
+        ));
+        keys.push(Span::styled(
+            match action {
+                Action::Submit => "write Cargo.toml",
+                _ => action.description(),
+            },
+            theme.dim_text,
+        ));
+    }
+
+    let width = area.width.saturating_sub(8).min(100);
+    let area = centered_rect(area, width, lines.len() as u16 + 4);
+    let chunks = Layout::default()
+        .margin(1)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(area);
+    preview.scroll = preview
+        .scroll
+        .min((lines.len() as u16).saturating_sub(chunks[0].height));
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Block::default()
+            .title(Span::styled(
+                format!(" {} ", edit.description),
+                theme.heading,
+            ))
+            .borders(Borders::ALL)
+            .border_style(theme.border),
+        area,
+    );
+    f.render_widget(
+        Paragraph::new(Text::from(lines)).scroll((preview.scroll, 0)),
+        chunks[0],
+    );
+    f.render_widget(Paragraph::new(Spans::from(keys)), chunks[1]);
+}
@@ -1,35 +1,70 @@
 use crate::{
     app::App,
-    cargo::{self, search::Crate, SearchResponse},
-    ui::rgb,
+    cargo::{self, search::Crate},
+    keymap::{self, Action},
+    line_editor::LineEditor,
+    pages::compare,
+    worker::{Request, Response},
 };
 use anyhow::Result;
-use crossterm::event::{Event, KeyCode, MouseEventKind};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
 use smart_default::SmartDefault;
-use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    style::Style,
     text::{Span, Spans, Text},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
+
+const SHORT_HELP: &[(&[Action], &str)] = &[
+    (
+        &[
+            Action::OpenCratesIo,
+            Action::OpenDocs,
+            Action::OpenRepository,
+        ],
+        "links",
+    ),
+    (&[Action::Add], "add"),
+    (&[Action::Install], "install"),
+    (&[Action::Favourite], "favourite"),
+    (&[Action::Mark, Action::Compare], "compare"),
+    (&[Action::Help], "help"),
+];
 
 #[derive(Default, Debug)]
 pub struct SearchPage {
     query_state: QueryState,
     results_state: Option<ResultsState>,
     per_page: usize,
-    expanded_help_message: bool,
+    // Where things were last drawn, used for mouse hit testing
+    query_rect: Rect,
+    result_rects: Vec<Rect>,
+    dot_rects: Vec<Rect>,
 }
 
 #[derive(SmartDefault, Debug)]
 pub struct QueryState {
     #[default(true)]
     active: bool,
-    query: String,
-    cursor: usize,
+    query: LineEditor,
+    /// When the query was last changed, used to debounce live searches
+    edited: Option<Instant>,
+    /// Position while stepping through the history and the query from before
+    history_index: Option<usize>,
+    draft: String,
+    reverse_search: Option<ReverseSearch>,
+}
+
+/// An incremental search backwards through the history
+#[derive(Default, Debug)]
+struct ReverseSearch {
+    pattern: String,
+    found: Option<usize>,
 }
 
 #[derive(Default, Debug)]
@@ -39,12 +74,32 @@ pub struct ResultsState {
     results: Vec<Vec<Crate>>,
     query: String,
     loaded_all: bool,
-    request_handle: Option<JoinHandle<Result<SearchResponse>>>,
+    /// The page of results loading in the background
+    request: Option<Request>,
+    /// Pages fetched so far and how many crates each holds, which stays the
+    /// same for the whole search so fetches line up however the list is resized
+    fetched: usize,
+    fetch_limit: usize,
+}
+
+impl ResultsState {
+    /// Splits the loaded crates into pages of `per_page` with any new ones on the end
+    fn rechunk(&mut self, per_page: usize, new: Vec<Crate>) {
+        let mut crates: Vec<Crate> = self.results.drain(..).flatten().collect();
+        crates.extend(new);
+        self.results = crates
+            .chunks(per_page.max(1))
+            .map(<[Crate]>::to_vec)
+            .collect();
+    }
 }
 
 impl SearchPage {
     fn next_index(&mut self) {
         if let Some(state) = &mut self.results_state {
+            if state.results.is_empty() {
+                return;
+            }
             if state.index < state.results[state.page].len() - 1 {
                 state.index += 1;
             } else if state.page < state.results.len() - 1 {
@@ -58,6 +113,9 @@ impl SearchPage {
     }
     fn previous_index(&mut self) {
         if let Some(state) = &mut self.results_state {
+            if state.results.is_empty() {
+                return;
+            }
             if state.index > 0 {
                 state.index -= 1;
             } else if state.page > 0 {
@@ -72,6 +130,9 @@ impl SearchPage {
     }
     fn next_page(&mut self) {
         if let Some(state) = &mut self.results_state {
+            if state.results.is_empty() {
+                return;
+            }
             let last_page = state.results.len() - 1;
             if state.page < last_page {
                 state.page += 1;
@@ -85,6 +146,9 @@ impl SearchPage {
     }
     fn previous_page(&mut self) {
         if let Some(state) = &mut self.results_state {
+            if state.results.is_empty() {
+                return;
+            }
             let last_page = state.results.len() - 1;
             if state.page > 0 {
                 state.page -= 1;
@@ -96,165 +160,474 @@ impl SearchPage {
             }
         }
     }
+    /// Returns whether the selection changed
+    fn select(&mut self, index: usize) -> bool {
+        match &mut self.results_state {
+            Some(state)
+                if index != state.index
+                    && index < state.results.get(state.page).map_or(0, |p| p.len()) =>
+            {
+                state.index = index;
+                true
+            }
+            _ => false,
+        }
+    }
+    fn go_to_page(&mut self, page: usize) {
+        if let Some(state) = &mut self.results_state {
+            if let Some(crates) = state.results.get(page) {
+                state.page = page;
+                state.index = state.index.min(crates.len().saturating_sub(1));
+            }
+        }
+    }
+    /// Splits the loaded results into pages that fit the list
+    fn set_per_page(&mut self, per_page: usize) {
+        if per_page == self.per_page {
+            return;
+        }
+        let old_per_page = std::mem::replace(&mut self.per_page, per_page);
+        if let Some(state) = &mut self.results_state {
+            let selected = state.page * old_per_page.max(1) + state.index;
+            state.rechunk(per_page, vec![]);
+            state.page = selected / per_page;
+            state.index = selected % per_page;
+            if state.page >= state.results.len() {
+                state.page = 0;
+                state.index = 0;
+            }
+        }
+    }
     fn selected_crate(&self) -> Option<Crate> {
         let state = self.results_state.as_ref()?;
         Some(state.results.get(state.page)?.get(state.index)?.to_owned())
     }
+    /// Every crate loaded by the current search
+    pub fn crate_ids(&self) -> Vec<String> {
+        self.results_state
+            .iter()
+            .flat_map(|state| state.results.iter().flatten())
+            .map(|c| c.id.to_owned())
+            .collect()
+    }
+    /// The crates on the page being shown
+    pub fn visible_ids(&self) -> Vec<String> {
+        self.results_state
+            .iter()
+            .filter_map(|state| state.results.get(state.page))
+            .flatten()
+            .map(|c| c.id.to_owned())
+            .collect()
+    }
 }
 
 pub fn event(app: &mut App, e: Event) -> bool {
     if let Event::Key(key) = e {
-        if app.search_page.query_state.active {
+        if app.search_page.query_state.reverse_search.is_some() {
+            return reverse_search_event(app, key);
+        } else if app.search_page.query_state.active {
             let state = &mut app.search_page.query_state;
-            match key.code {
-                KeyCode::Char(c) => {
-                    state.query.push(c);
-                    state.cursor += 1;
-                }
-                KeyCode::Backspace => {
-                    if state.cursor > 0 {
-                        state.query.remove(state.cursor - 1);
-                        state.cursor -= 1;
-                    }
-                }
-                KeyCode::Delete => {
-                    if state.cursor < state.query.len() {
-                        state.query.remove(state.cursor);
-                    }
+            let previous_query = state.query.text().to_owned();
+            let action = app
+                .config
+                .keys
+                .find(&key, keymap::SEARCH_BOX.actions)
+                .or_else(|| app.config.keys.find(&key, keymap::EDITING.actions));
+            if !matches!(action, Some(Action::HistoryPrevious | Action::HistoryNext)) {
+                state.history_index = None;
+            }
+            match action {
+                Some(Action::HistoryPrevious) => {
+                    let index = match state.history_index {
+                        Some(index) => index.saturating_sub(1),
+                        None if app.history.len() > 0 => {
+                            state.draft = state.query.text().to_owned();
+                            app.history.len() - 1
+                        }
+                        None => return false,
+                    };
+                    state.history_index = Some(index);
+                    let query = app.history.get(index).unwrap_or_default().to_owned();
+                    state.query.set_text(query);
                 }
-                KeyCode::Left => {
-                    if state.cursor > 0 {
-                        state.cursor -= 1;
+                Some(Action::HistoryNext) => {
+                    if let Some(index) = state.history_index {
+                        if index + 1 < app.history.len() {
+                            state.history_index = Some(index + 1);
+                            let query = app.history.get(index + 1).unwrap_or_default().to_owned();
+                            state.query.set_text(query);
+                        } else {
+                            state.history_index = None;
+                            let draft = std::mem::take(&mut state.draft);
+                            state.query.set_text(draft);
+                        }
                     }
                 }
-                KeyCode::Right => {
-                    if state.cursor < state.query.len() {
-                        state.cursor += 1;
-                    }
+                Some(Action::HistorySearch) => {
+                    state.reverse_search = Some(ReverseSearch::default());
                 }
-                KeyCode::Enter => {
-                    app.search_page.results_state = Some(ResultsState {
-                        index: 0,
-                        page: 0,
-                        results: vec![],
-                        query: state.query.to_owned(),
-                        loaded_all: false,
-                        request_handle: None,
-                    });
+                Some(Action::Submit) => submit_query(app),
+                Some(Action::Cancel) => {
                     state.active = false;
                 }
-                KeyCode::Esc => {
-                    state.active = false;
+                action => {
+                    if !state.query.handle(action, &key) {
+                        return true;
+                    }
                 }
-                _ => return true,
+            }
+
+            let state = &mut app.search_page.query_state;
+            if app.config.search.live && state.query.text() != previous_query {
+                state.edited = Some(Instant::now());
             }
         } else {
-            match key.code {
-                KeyCode::Char('s') => {
+            match app.config.keys.find(&key, keymap::SEARCH.actions) {
+                Some(Action::Search) => {
                     app.search_page.query_state.active = true;
                 }
-                KeyCode::Char('?') => {
-                    app.search_page.expanded_help_message = !app.search_page.expanded_help_message
-                }
                 // Movement
-                KeyCode::Up | KeyCode::Char('k') => app.search_page.previous_index(),
-                KeyCode::Down | KeyCode::Char('j') => app.search_page.next_index(),
-                KeyCode::Left | KeyCode::Char('h') => app.search_page.previous_page(),
-                KeyCode::Right | KeyCode::Char('l') => app.search_page.next_page(),
-                // Links
-                KeyCode::Char('c') => {
-                    if let Some(c) = app.search_page.selected_crate() {
-                        open::that(format!("https://crates.io/crates/{}", c.id))
-                            .unwrap_or_else(|_| app.error());
-                    } else {
-                        app.warn();
-                    }
-                }
-                KeyCode::Char('d') => {
-                    if let Some(c) = app.search_page.selected_crate() {
-                        open::that(format!("https://docs.rs/{}/latest", c.id))
-                            .unwrap_or_else(|_| app.error());
-                    } else {
-                        app.warn();
-                    }
-                }
-                KeyCode::Char('g') => {
-                    if let Some(Some(repo)) = app.search_page.selected_crate().map(|c| c.repository)
-                    {
-                        open::that(repo).unwrap_or_else(|_| app.error());
-                    } else {
-                        app.warn();
-                    }
-                }
-                // Actions
-                KeyCode::Char('a') => {
-                    if let Some(c) = app.search_page.selected_crate() {
-                        cargo::add(c.id, c.max_stable_version.unwrap_or(c.max_version))
-                            .unwrap_or_else(|_| app.error());
-                    } else {
-                        app.warn();
-                    }
-                }
-                KeyCode::Char('r') => {
-                    if let Some(c) = app.search_page.selected_crate() {
-                        cargo::remove(c.id).unwrap_or_else(|_| app.error());
-                    } else {
-                        app.warn();
-                    }
-                }
-                KeyCode::Char('i') => {
-                    if let Some(c) = app.search_page.selected_crate() {
-                        cargo::install(c.id).unwrap();
-                    } else {
-                        app.warn();
-                    }
-                }
-                KeyCode::Char('f') => {
-                    if let Some(c) = app.search_page.selected_crate() {
-                        app.config.favourites.crates.push(c.id);
-                    } else {
-                        app.warn();
-                    }
+                Some(Action::Up) => app.search_page.previous_index(),
+                Some(Action::Down) => app.search_page.next_index(),
+                Some(Action::PreviousPage) => app.search_page.previous_page(),
+                Some(Action::NextPage) => app.search_page.next_page(),
+                Some(Action::Details) => match app.search_page.selected_crate() {
+                    Some(c) => app.details_page.show(c),
+                    None => app.warn(),
+                },
+                Some(Action::Mark) => match app.search_page.selected_crate() {
+                    Some(c) => compare::toggle_mark(app, c.id),
+                    None => app.warn(),
+                },
+                Some(Action::Compare) => compare::open(app),
+                Some(action) => {
+                    let c = app.search_page.selected_crate();
+                    crate_action(app, action, c);
                 }
                 _ => return true,
             }
         }
-    } else if let Event::Resize(_, height) = e {
-        if height < 13 {
+    } else if let Event::Paste(text) = e {
+        let state = &mut app.search_page.query_state;
+        if let Some(search) = state.reverse_search.as_mut() {
+            search.pattern.push_str(&text);
+            search.found = app.history.find_before(&search.pattern, app.history.len());
+        } else if state.active {
+            state.query.insert_str(&text);
+            if app.config.search.live {
+                state.edited = Some(Instant::now());
+            }
+        } else {
             return true;
         }
-        app.search_page.per_page = (height as usize - 13) / 4 + 1;
-        if let Some(state) = &mut app.search_page.results_state {
-            let crates: Vec<Crate> = state.results.iter().flatten().cloned().collect();
-            let pages = crates
-                .chunks(app.search_page.per_page)
-                .map(|chunk| chunk.to_vec())
-                .collect();
-            state.results = pages;
-        }
     } else if let Event::Mouse(e) = e {
         if !app.config.mouse.enabled {
             return true;
         }
-        if let MouseEventKind::Moved = e.kind {
-            if e.row < 7 {
-                return false;
+        let hit = |rects: &[Rect]| {
+            rects
+                .iter()
+                .position(|r| r.intersects(Rect::new(e.column, e.row, 1, 1)))
+        };
+        match e.kind {
+            MouseEventKind::Moved => match hit(&app.search_page.result_rects) {
+                Some(index) if app.search_page.select(index) => {}
+                _ => return true,
+            },
+            MouseEventKind::Down(MouseButton::Left) => {
+                let double_click = app.is_double_click(e);
+                if let Some(index) = hit(&app.search_page.result_rects) {
+                    app.search_page.select(index);
+                    if double_click {
+                        if let Some(c) = app.search_page.selected_crate() {
+                            app.details_page.show(c);
+                        }
+                    }
+                } else if let Some(page) = hit(&app.search_page.dot_rects) {
+                    app.search_page.go_to_page(page);
+                } else if hit(&[app.search_page.query_rect]).is_some() {
+                    app.search_page.query_state.active = true;
+                } else {
+                    return true;
+                }
+            }
+            MouseEventKind::ScrollDown => app.search_page.next_page(),
+            MouseEventKind::ScrollUp => app.search_page.previous_page(),
+            _ => return true,
+        }
+    }
+
+    false
+}
+
+fn submit_query(app: &mut App) {
+    let state = &mut app.search_page.query_state;
+    state.active = false;
+    state.edited = None;
+    let query = state.query.text().to_owned();
+    app.history.push(&query).unwrap_or_else(|_| app.error());
+    search(app, query);
+}
+
+/// Starts a new search, cancelling any results still loading for the last one
+pub fn search(app: &mut App, query: String) {
+    let old = app.search_page.results_state.replace(ResultsState {
+        query,
+        ..Default::default()
+    });
+    if let Some(request) = old.and_then(|s| s.request) {
+        app.worker.cancel(&request);
+    }
+}
+
+/// Runs the last search again, used when the sort order changes
+pub fn refresh(app: &mut App) {
+    if let Some(query) = app
+        .search_page
+        .results_state
+        .as_ref()
+        .map(|s| s.query.to_owned())
+    {
+        search(app, query);
+    }
+}
+
+/// Starts a live search once typing has paused and loads pages of results
+/// until there are no more, returns whether a search started
+pub fn poll(app: &mut App) -> bool {
+    let mut searched = false;
+    let query_state = &mut app.search_page.query_state;
+    let debounce = Duration::from_millis(app.config.search.debounce_ms);
+    if query_state
+        .edited
+        .is_some_and(|edited| edited.elapsed() >= debounce)
+    {
+        query_state.edited = None;
+        let query = query_state.query.text().trim().to_owned();
+        let current = app
+            .search_page
+            .results_state
+            .as_ref()
+            .map(|s| s.query.as_str());
+        if !query.is_empty() && current != Some(query.as_str()) {
+            search(app, query);
+            searched = true;
+        }
+    }
+
+    // The page size is only known once the results have been drawn
+    let per_page = app.search_page.per_page;
+    let Some(state) = app.search_page.results_state.as_mut() else {
+        return searched;
+    };
+    if state.request.is_none() && !state.loaded_all && per_page > 0 {
+        if state.fetch_limit == 0 {
+            state.fetch_limit = per_page;
+        }
+        let request = Request::Search {
+            query: state.query.to_owned(),
+            page: state.fetched,
+            limit: state.fetch_limit,
+            sort: app.config.search.sort,
+        };
+        app.worker.send(request.to_owned());
+        state.request = Some(request);
+    }
+    searched
+}
+
+/// How long until a live search is due, so the app can wake up for it
+pub fn debounce_remaining(app: &App) -> Option<Duration> {
+    let debounce = Duration::from_millis(app.config.search.debounce_ms);
+    let edited = app.search_page.query_state.edited?;
+    Some(debounce.saturating_sub(edited.elapsed()))
+}
+
+/// Adds a page of results, anything for an older search has already been cancelled
+pub fn receive(app: &mut App, request: Request, response: Result<Response>) {
+    let Some(state) = app.search_page.results_state.as_mut() else {
+        return;
+    };
+    if state.request.as_ref() != Some(&request) {
+        return;
+    }
+    state.request = None;
+    match response {
+        Ok(Response::Search(res)) => {
+            state.fetched += 1;
+            state.rechunk(app.search_page.per_page, res.crates);
+            state.loaded_all = res.meta.next_page.is_none()
+                || state.fetched >= app.config.search.max_pages as usize;
+        }
+        Ok(_) => state.loaded_all = true,
+        Err(e) => {
+            state.loaded_all = true;
+            app.error();
+            app.command_line.error(format!("Search failed: {e}"));
+        }
+    }
+}
+
+/// Typing narrows the search, the history search key finds older matches
+fn reverse_search_event(app: &mut App, key: KeyEvent) -> bool {
+    let state = &mut app.search_page.query_state;
+    let Some(search) = state.reverse_search.as_mut() else {
+        return true;
+    };
+    let action = app
+        .config
+        .keys
+        .find(&key, keymap::SEARCH_BOX.actions)
+        .or_else(|| app.config.keys.find(&key, keymap::EDITING.actions));
+    match action {
+        Some(Action::HistorySearch) => {
+            let before = search.found.unwrap_or(app.history.len());
+            if let Some(found) = app.history.find_before(&search.pattern, before) {
+                search.found = Some(found);
             }
-            let index = (e.row - 7) / 4;
-            if let Some(state) = &mut app.search_page.results_state {
-                if state.results.is_empty() {
+        }
+        Some(Action::DeleteBack) => {
+            search.pattern.pop();
+            search.found = app.history.find_before(&search.pattern, app.history.len());
+        }
+        Some(Action::Cancel) => state.reverse_search = None,
+        action => {
+            // Anything else accepts the match into the query
+            if let KeyCode::Char(c) = key.code {
+                if action.is_none()
+                    && !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                {
+                    search.pattern.push(c);
+                    search.found = app.history.find_before(&search.pattern, app.history.len());
                     return false;
                 }
-                if state.results[state.page].len() as u16 > index {
-                    state.index = index as usize;
-                }
+            }
+            if let Some(found) = search.found.and_then(|i| app.history.get(i)) {
+                state.query.set_text(found.to_owned());
+            }
+            state.reverse_search = None;
+            if action == Some(Action::Submit) {
+                submit_query(app);
             }
         }
     }
-
     false
 }
 
+/// The license of the latest version once the crate's info has been fetched
+pub fn license<'a>(app: &'a App, id: &str) -> Option<&'a str> {
+    app.info_cache
+        .get(id)
+        .and_then(|info| info.latest())
+        .and_then(|v| v.license.as_deref())
+}
+
+/// A note on whether the latest version works with the project's `rust-version`
+/// and whether it's a problem, `None` until the info is fetched or if it has no MSRV
+pub fn msrv(app: &App, id: &str) -> Option<(String, bool)> {
+    let info = app.info_cache.get(id)?;
+    let latest = info.latest()?;
+    let msrv = latest.rust_version.as_deref()?;
+    let Some(rust_version) = &app.rust_version else {
+        return Some((format!("MSRV {msrv}"), false));
+    };
+    if latest.is_compatible(Some(rust_version)) {
+        return Some((format!("MSRV {msrv} ✓"), false));
+    }
+    match info.latest_compatible(Some(rust_version)) {
+        Some(v) if v.is_compatible(Some(rust_version)) => {
+            Some((format!("⚠ needs Rust {msrv}, {} fits", v.num), true))
+        }
+        _ => Some((format!("⚠ needs Rust {msrv}"), true)),
+    }
+}
+
+/// A short note on a crate's advisories, `None` if it has none
+pub fn advisory_spans(
+    app: &App,
+    c: &Crate,
+    warning_style: Style,
+    dim_style: Style,
+) -> Option<Vec<Span<'static>>> {
+    let advisories = app.advisories.for_crate(&c.id);
+    if advisories.is_empty() {
+        return None;
+    }
+    let version = c.max_stable_version.as_ref().unwrap_or(&c.max_version);
+    let latest = app
+        .advisories
+        .affecting(&c.id, version)
+        .iter()
+        .any(|a| a.is_vulnerability());
+    let count = match advisories.len() {
+        1 => "1 advisory".to_owned(),
+        n => format!("{n} advisories"),
+    };
+    Some(vec![
+        Span::styled(" • ", dim_style),
+        if latest {
+            Span::styled(format!("⚠ {count}, latest affected"), warning_style)
+        } else {
+            Span::styled(count, dim_style)
+        },
+    ])
+}
+
+/// Previews adding a version along with any warnings about it, errors finding
+/// the version or from the license policy are shown instead
+pub fn add_version(
+    app: &mut App,
+    id: String,
+    version: Result<String>,
+    features: &[String],
+) -> bool {
+    let edit = version.and_then(|version| {
+        let warnings = app.add_warnings(&id, &version)?;
+        Ok((cargo::add(id, version, features)?, warnings))
+    });
+    match edit {
+        Ok((edit, warnings)) => app.preview_edit(Ok(edit), warnings),
+        Err(e) => app.preview_edit(Err(e), vec![]),
+    }
+}
+
+/// Runs an action that applies to a single crate, returns false if it wasn't one
+pub fn crate_action(app: &mut App, action: Action, c: Option<Crate>) -> bool {
+    let Some(c) = c else {
+        app.warn();
+        return true;
+    };
+    match action {
+        // Links
+        Action::OpenCratesIo => app.open(&c.crates_io_url()),
+        Action::OpenDocs => app.open(&c.docs_url()),
+        Action::OpenRepository => match c.repository {
+            Some(repo) => app.open(&repo),
+            None => app.warn(),
+        },
+        // Actions
+        Action::Add => app.add_crate(c),
+        Action::Versions => app.pick_version(c.id),
+        Action::Remove => {
+            app.preview_edit(cargo::remove(c.id), vec![]);
+        }
+        Action::Install => cargo::install(c.id).unwrap_or_else(|_| app.error()),
+        Action::Favourite => {
+            let favourites = &mut app.config.favourites.crates;
+            match favourites.iter().position(|f| *f == c.id) {
+                Some(i) => {
+                    favourites.remove(i);
+                }
+                None => favourites.push(c.id),
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
 pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     let chunks = Layout::default()
         .vertical_margin(1)
@@ -265,51 +638,60 @@ pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
         ])
         .split(area);
 
+    // Each result takes three lines plus a blank line, the last one doesn't need the blank
+    app.search_page
+        .set_per_page(((chunks[1].height as usize + 1) / 4).max(1));
+    app.search_page.result_rects.clear();
+    app.search_page.dot_rects.clear();
+
+    // The box grows with the query until it fills the line, then scrolls
+    let available = chunks[0].width.saturating_sub(6) as usize;
     let query_state = &mut app.search_page.query_state;
+    let (text, cursor) = match &query_state.reverse_search {
+        Some(search) => {
+            let found = search.found.and_then(|i| app.history.get(i));
+            let prefix = format!("(reverse-i-search)'{}': ", search.pattern);
+            let cursor = prefix.width();
+            (format!("{prefix}{}", found.unwrap_or_default()), cursor)
+        }
+        None => {
+            let width = (query_state.query.width() + 1).min(available);
+            query_state.query.view(width)
+        }
+    };
 
     let search_bar_chunk = Layout::default()
         .horizontal_margin(3)
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(query_state.query.len().max(9) as u16 + 1),
+            Constraint::Length((text.width().max(9) + 1).min(available) as u16),
             Constraint::Min(0),
         ])
         .split(chunks[0])[0];
+    app.search_page.query_rect = search_bar_chunk;
 
-    let search_style = if query_state.active {
-        Style::default().fg(rgb(app.config.colors.primary))
+    let theme = &app.theme;
+    let (search_style, border_style) = if query_state.active {
+        (theme.input, theme.input)
     } else {
-        Style::default()
+        (theme.text, theme.border)
     };
 
-    let text = Text::styled(&query_state.query, Style::default());
+    let text = Text::styled(text, Style::default());
     f.render_widget(
         Paragraph::new(text)
-            .block(Block::default().borders(Borders::BOTTOM))
+            .block(
+                Block::default()
+                    .borders(Borders::BOTTOM)
+                    .border_style(border_style),
+            )
             .style(search_style),
         search_bar_chunk,
     );
 
     if query_state.active {
-        f.set_cursor(query_state.cursor as u16 + 3, 5);
-    }
-
-    if let Some(state) = app.search_page.results_state.as_mut() {
-        if let Some(handle) = state.request_handle.as_mut() {
-            if handle.is_finished() {
-                let handle = state.request_handle.take().unwrap();
-                let res = handle.join().unwrap().unwrap();
-                state.results.push(res.crates);
-                state.loaded_all = res.meta.next_page.is_none()
-                    || state.results.len() >= app.config.search.max_pages as usize;
-            }
-        } else if !state.loaded_all {
-            let query = state.query.to_owned();
-            let page = state.results.len();
-            let limit = app.search_page.per_page;
-            let handle = thread::spawn(move || cargo::search(&query, page, limit));
-            state.request_handle = Some(handle);
-        }
+        let cursor = (cursor as u16).min(search_bar_chunk.width.saturating_sub(1));
+        f.set_cursor(search_bar_chunk.x + cursor, search_bar_chunk.y);
     }
 
     let results_state = match &app.search_page.results_state {
@@ -326,22 +708,37 @@ pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
         .split(chunks[1]);
 
     let crates = &results_state.results[results_state.page];
+    let theme = &app.theme;
+    let mut result_rects = vec![];
     for (i, c) in crates.iter().enumerate() {
         let selected = i == results_state.index;
 
-        let style = if selected {
-            Style::default().fg(rgb(app.config.colors.primary))
-        } else {
-            Style::default()
+        // Selection colours are layered over each element's own style
+        let with_selection = |style: Style| {
+            if selected {
+                style.patch(theme.selection)
+            } else {
+                style
+            }
         };
+        let style = with_selection(theme.text);
+        let dim_style = with_selection(theme.dim_text);
 
         let prefix = if selected { " │ " } else { "   " };
 
-        let text = Text {
+        let mut text = Text {
             lines: vec![
                 Spans::from(vec![
                     Span::styled(prefix, style),
-                    Span::styled(c.id.to_owned(), style.add_modifier(Modifier::BOLD)),
+                    Span::styled(c.id.to_owned(), with_selection(theme.heading)),
+                    Span::styled(
+                        if app.compare_page.is_marked(&c.id) {
+                            " ✓"
+                        } else {
+                            ""
+                        },
+                        dim_style,
+                    ),
                 ]),
                 Spans::from(vec![
                     Span::styled(prefix, style),
@@ -354,115 +751,196 @@ pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
                 ]),
                 Spans::from(vec![
                     Span::styled(prefix, style),
-                    Span::styled("Downloads: ", style.add_modifier(Modifier::DIM)),
-                    Span::styled(c.downloads.to_string(), style.add_modifier(Modifier::DIM)),
-                    Span::styled(" • ", style.add_modifier(Modifier::DIM)),
-                    Span::styled("Recent: ", style.add_modifier(Modifier::DIM)),
-                    Span::styled(
-                        c.recent_downloads.to_string(),
-                        style.add_modifier(Modifier::DIM),
-                    ),
+                    Span::styled("Downloads: ", dim_style),
+                    Span::styled(c.downloads.to_string(), dim_style),
+                    Span::styled(" • ", dim_style),
+                    Span::styled("Recent: ", dim_style),
+                    Span::styled(c.recent_downloads.to_string(), dim_style),
                 ]),
             ],
         };
+        if let Some(license) = license(app, &c.id) {
+            let style = match app.config.policy.allows(Some(license)) {
+                true => dim_style,
+                false => with_selection(theme.warning),
+            };
+            text.lines[2].0.extend([
+                Span::styled(" • ", dim_style),
+                Span::styled(format!("License: {license}"), style),
+            ]);
+        }
+        if let Some((msrv, warn)) = msrv(app, &c.id) {
+            text.lines[2].0.extend([
+                Span::styled(" • ", dim_style),
+                Span::styled(
+                    msrv,
+                    match warn {
+                        true => with_selection(theme.warning),
+                        false => dim_style,
+                    },
+                ),
+            ]);
+        }
+        if let Some(spans) = advisory_spans(app, c, with_selection(theme.warning), dim_style) {
+            text.lines[2].0.extend(spans);
+        }
 
         f.render_widget(Paragraph::new(text), list_chunks[i]);
+        result_rects.push(Rect {
+            height: 3,
+            ..list_chunks[i]
+        });
     }
 
-    if !app.search_page.expanded_help_message {
-        let footer_chunks = Layout::default()
-            .horizontal_margin(3)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(chunks[2]);
-
-        let mut dots = vec![];
-        for i in 0..results_state.results.len() {
-            if i == results_state.page {
-                dots.push(Span::styled(
-                    "• ",
-                    Style::default().add_modifier(Modifier::BOLD),
-                ));
-            } else {
-                dots.push(Span::styled(
-                    "• ",
-                    Style::default().add_modifier(Modifier::DIM),
-                ));
-            }
+    let footer_chunks = Layout::default()
+        .horizontal_margin(3)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .split(chunks[2]);
+
+    let mut dots = vec![];
+    let mut dot_rects = vec![];
+    for i in 0..results_state.results.len() {
+        dot_rects.push(Rect::new(
+            footer_chunks[0].x + i as u16 * 2,
+            footer_chunks[0].y,
+            2,
+            1,
+        ));
+        if i == results_state.page {
+            dots.push(Span::styled("• ", theme.heading));
+        } else {
+            dots.push(Span::styled("• ", theme.dim_text));
         }
-        f.render_widget(Paragraph::new(Spans::from(dots)), footer_chunks[0]);
-
-        let help_text = Text::from(Spans::from(vec![
-            Span::styled(
-                "c/d/g ",
-                Style::default().add_modifier(Modifier::BOLD | Modifier::DIM),
-            ),
-            Span::styled("links", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(" • ", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(
-                "a ",
-                Style::default().add_modifier(Modifier::BOLD | Modifier::DIM),
-            ),
-            Span::styled("add", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(" • ", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(
-                "i ",
-                Style::default().add_modifier(Modifier::BOLD | Modifier::DIM),
-            ),
-            Span::styled("install", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(" • ", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(
-                "f ",
-                Style::default().add_modifier(Modifier::BOLD | Modifier::DIM),
-            ),
-            Span::styled("favourite", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(" • ", Style::default().add_modifier(Modifier::DIM)),
-            Span::styled(
-                "? ",
-                Style::default().add_modifier(Modifier::BOLD | Modifier::DIM),
-            ),
-            Span::styled("help", Style::default().add_modifier(Modifier::DIM)),
-        ]));
-        f.render_widget(Paragraph::new(help_text), footer_chunks[2]);
-    } else {
-        let footer_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .horizontal_margin(3)
-            .constraints([Constraint::Length(4), Constraint::Length(15)].repeat(3))
-            .split(chunks[2]);
-
-        let keybinds = [
-            vec![("c", "crates.io"), ("d", "docs.rs"), ("g", "git repo")],
-            vec![("a", "add"), ("r", "remove"), ("i", "install")],
-            vec![("f", "favourite"), ("q", "quit"), ("?", "close help")],
-        ];
+    }
+    f.render_widget(Paragraph::new(Spans::from(dots)), footer_chunks[0]);
 
-        let key_style = Style::default().add_modifier(Modifier::BOLD);
-        let description_style = Style::default().add_modifier(Modifier::DIM);
+    let key_style = theme.key;
+    let description_style = theme.dim_text;
 
-        for (i, k) in keybinds.iter().enumerate() {
-            let i = i * 2;
+    let mut help_spans = vec![];
+    for (i, (actions, description)) in SHORT_HELP.iter().enumerate() {
+        if i > 0 {
+            help_spans.push(Span::styled(" • ", description_style));
+        }
+        let keys: Vec<String> = actions
+            .iter()
+            .map(|action| app.config.keys.get(*action).primary())
+            .collect();
+        help_spans.push(Span::styled(format!("{} ", keys.join("/")), key_style));
+        help_spans.push(Span::styled(*description, description_style));
+    }
+    let help_text = Text::from(Spans::from(help_spans));
+    f.render_widget(Paragraph::new(help_text), footer_chunks[2]);
 
-            let keys = Text {
-                lines: k
-                    .iter()
-                    .map(|(key, _)| Spans::from(Span::styled(*key, key_style)))
-                    .collect(),
-            };
-            f.render_widget(Paragraph::new(keys), footer_chunks[i]);
-
-            let descriptions = Text {
-                lines: k
-                    .iter()
-                    .map(|(_, description)| {
-                        Spans::from(Span::styled(*description, description_style))
-                    })
-                    .collect(),
-            };
-            f.render_widget(Paragraph::new(descriptions), footer_chunks[i + 1]);
+    app.search_page.result_rects = result_rects;
+    app.search_page.dot_rects = dot_rects;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cargo::{Meta, SearchResponse};
+
+    fn crates(range: std::ops::Range<usize>) -> Vec<Crate> {
+        range
+            .map(|i| Crate {
+                id: i.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn page_with(loaded: usize, per_page: usize, page: usize, index: usize) -> SearchPage {
+        let mut state = ResultsState {
+            page,
+            index,
+            ..Default::default()
+        };
+        state.rechunk(per_page, crates(0..loaded));
+        SearchPage {
+            results_state: Some(state),
+            per_page,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resizing_keeps_the_selected_crate() {
+        // (loaded, old per page, page, index, new per page, selected)
+        let cases = [
+            (20, 5, 2, 3, 6, "13"),
+            (20, 6, 2, 1, 4, "13"),
+            (20, 5, 0, 0, 3, "0"),
+            (20, 5, 3, 4, 7, "19"),
+            (20, 4, 4, 3, 20, "19"),
+            (7, 3, 2, 0, 1, "6"),
+        ];
+        for (loaded, old, page, index, new, selected) in cases {
+            let mut search_page = page_with(loaded, old, page, index);
+            assert_eq!(search_page.selected_crate().unwrap().id, selected);
+            search_page.set_per_page(new);
+            let state = search_page.results_state.as_ref().unwrap();
+            assert!(state.results.iter().all(|p| p.len() <= new));
+            assert_eq!(state.results.concat(), crates(0..loaded));
+            assert_eq!(
+                search_page.selected_crate().unwrap().id,
+                selected,
+                "{old} to {new} per page"
+            );
         }
     }
+
+    #[test]
+    fn fetches_line_up_after_resizing() {
+        let mut app = App::default();
+        app.search_page = page_with(0, 5, 0, 0);
+        let next_fetch = |app: &mut App| {
+            poll(app);
+            let state = app.search_page.results_state.as_mut().unwrap();
+            state.request.take().unwrap()
+        };
+        let receive_page = |app: &mut App, request: Request, range| {
+            let state = app.search_page.results_state.as_mut().unwrap();
+            state.request = Some(request.to_owned());
+            let res = SearchResponse {
+                crates: crates(range),
+                meta: Meta {
+                    next_page: Some(String::new()),
+                    prev_page: None,
+                    total: 100,
+                },
+            };
+            receive(app, request, Ok(Response::Search(res)));
+        };
+
+        let first = next_fetch(&mut app);
+        assert!(matches!(
+            first,
+            Request::Search {
+                page: 0,
+                limit: 5,
+                ..
+            }
+        ));
+        receive_page(&mut app, first, 0..5);
+        app.search_page.set_per_page(3);
+        let second = next_fetch(&mut app);
+        // Still 5 a page, so the second page starts where the first ended
+        assert!(matches!(
+            second,
+            Request::Search {
+                page: 1,
+                limit: 5,
+                ..
+            }
+        ));
+        receive_page(&mut app, second, 5..10);
+        let state = app.search_page.results_state.as_ref().unwrap();
+        assert_eq!(state.results.concat(), crates(0..10));
+        assert_eq!(state.results.len(), 4);
+    }
 }
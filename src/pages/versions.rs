@@ -0,0 +1,177 @@
+use crate::{
+    app::App,
+    cargo::info::Version,
+    keymap::{self, Action},
+    pages::search,
+    ui::centered_rect,
+};
+use crossterm::event::Event;
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Style,
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Picks a specific version of a crate to add, yanked versions can't be picked
+#[derive(Default, Debug)]
+pub struct VersionPicker {
+    id: Option<String>,
+    index: usize,
+    /// Whether the selection has been moved to the default version yet
+    positioned: bool,
+}
+
+impl VersionPicker {
+    pub fn open(&mut self, id: String) {
+        *self = Self {
+            id: Some(id),
+            ..Default::default()
+        };
+    }
+    pub fn is_open(&self) -> bool {
+        self.id.is_some()
+    }
+}
+
+fn versions<'a>(app: &'a App, id: &str) -> &'a [Version] {
+    app.info_cache
+        .get(id)
+        .map(|info| info.versions.as_slice())
+        .unwrap_or_default()
+}
+
+/// The picker is modal so it consumes every event while open
+pub fn event(app: &mut App, e: Event) {
+    let (Event::Key(key), Some(id)) = (e, app.version_picker.id.to_owned()) else {
+        return;
+    };
+    let versions = versions(app, &id);
+    // Steps over yanked versions, staying put if there's nothing to step to
+    let step = |index: usize, forward: bool| {
+        let mut i = index;
+        loop {
+            i = match forward {
+                true if i + 1 < versions.len() => i + 1,
+                false if i > 0 => i - 1,
+                _ => return index,
+            };
+            if !versions[i].yanked {
+                return i;
+            }
+        }
+    };
+    let index = app.version_picker.index;
+
+    match app.config.keys.find(&key, keymap::VERSIONS.actions) {
+        Some(Action::Up) => app.version_picker.index = step(index, false),
+        Some(Action::Down) => app.version_picker.index = step(index, true),
+        Some(Action::Submit) => match versions.get(index).filter(|v| !v.yanked) {
+            Some(version) => {
+                let num = version.num.to_owned();
+                if search::add_version(app, id, Ok(num), &[]) {
+                    app.version_picker.id = None;
+                }
+            }
+            None => app.warn(),
+        },
+        Some(Action::Cancel) => app.version_picker.id = None,
+        _ => {}
+    }
+}
+
+pub fn update<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let Some(id) = app.version_picker.id.to_owned() else {
+        return;
+    };
+    let theme = &app.theme;
+    // Borrowed from the cache directly so the picker can still be changed
+    let info = app.info_cache.get(&id);
+    let versions = info
+        .map(|info| info.versions.as_slice())
+        .unwrap_or_default();
+
+    // Start on the version `add` would pick
+    let picker = &mut app.version_picker;
+    if let Some(info) = info.filter(|_| !picker.positioned) {
+        picker.positioned = true;
+        picker.index = info
+            .latest_compatible(app.rust_version.as_ref())
+            .and_then(|latest| versions.iter().position(|v| v.num == latest.num))
+            .unwrap_or(0);
+    }
+    if versions.get(picker.index).is_some_and(|v| v.yanked) {
+        picker.index = versions.iter().position(|v| !v.yanked).unwrap_or(0);
+    }
+
+    let area = centered_rect(area, 60, area.height.saturating_sub(4).min(24));
+    let height = area.height.saturating_sub(2) as usize;
+    let skip = (picker.index + 1).saturating_sub(height);
+
+    let mut lines = vec![];
+    for (i, version) in versions.iter().enumerate().skip(skip).take(height) {
+        let selected = i == picker.index;
+        let with_selection = |style: Style| match selected {
+            true => style.patch(theme.selection),
+            false => style,
+        };
+        let mut spans = vec![
+            Span::styled(
+                format!(
+                    "{}{:<16}",
+                    if selected { " │ " } else { "   " },
+                    version.num
+                ),
+                with_selection(if version.yanked {
+                    theme.dim_text
+                } else {
+                    theme.heading
+                }),
+            ),
+            Span::styled(
+                version.created_at.chars().take(10).collect::<String>(),
+                with_selection(theme.dim_text),
+            ),
+        ];
+        if let Some(msrv) = &version.rust_version {
+            let style = match version.is_compatible(app.rust_version.as_ref()) {
+                true => theme.dim_text,
+                false => theme.warning,
+            };
+            spans.push(Span::styled(
+                format!("  MSRV {msrv}"),
+                with_selection(style),
+            ));
+        }
+        if version.yanked {
+            spans.push(Span::styled("  yanked", with_selection(theme.dim_text)));
+        } else if app
+            .advisories
+            .affecting(&id, &version.num)
+            .iter()
+            .any(|a| a.is_vulnerability())
+        {
+            spans.push(Span::styled("  ⚠ advisory", with_selection(theme.warning)));
+        }
+        lines.push(Spans::from(spans));
+    }
+    if versions.is_empty() {
+        lines.push(Spans::from(Span::styled(
+            "   No versions found",
+            theme.dim_text,
+        )));
+    }
+
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(Text::from(lines)).block(
+            Block::default()
+                .title(Span::styled(format!(" {id} versions "), theme.heading))
+                .borders(Borders::ALL)
+                .border_style(theme.border),
+        ),
+        area,
+    );
+}
@@ -0,0 +1,121 @@
+use crate::config::Policy;
+use spdx::{Expression, Licensee, ParseMode};
+
+impl Policy {
+    pub fn is_enabled(&self) -> bool {
+        !self.allowed_licenses.is_empty()
+    }
+
+    /// Allowed licenses that aren't SPDX ids, these are left out of every check
+    pub fn errors(&self) -> Vec<String> {
+        self.allowed_licenses
+            .iter()
+            .filter_map(|l| Licensee::parse(l).err())
+            .map(|e| {
+                format!(
+                    "Ignoring '{}' in allowed_licenses, {}",
+                    e.original, e.reason
+                )
+            })
+            .collect()
+    }
+
+    /// Whether a license expression like `MIT OR Apache-2.0` can be satisfied
+    /// with the allowed licenses, crates without a license never can
+    pub fn allows(&self, license: Option<&str>) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        // Crates.io still has old licenses like `MIT/Apache-2.0`
+        let Some(expression) = license.and_then(|l| Expression::parse_mode(l, ParseMode::LAX).ok())
+        else {
+            return false;
+        };
+        let allowed: Vec<Licensee> = self
+            .allowed_licenses
+            .iter()
+            .filter_map(|l| Licensee::parse(l).ok())
+            .collect();
+        expression.evaluate(|req| allowed.iter().any(|l| l.satisfies(req)))
+    }
+
+    /// Explains why a crate isn't allowed, `None` if it is
+    pub fn violation(&self, id: &str, license: Option<&str>) -> Option<String> {
+        match self.allows(license) {
+            true => None,
+            false => Some(format!(
+                "{id} is licensed under {}, which the license policy doesn't allow",
+                license.unwrap_or("an unknown license")
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed: &[&str]) -> Policy {
+        Policy {
+            allowed_licenses: allowed.iter().map(|l| l.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn allows() {
+        let cases = [
+            (&[][..], None, true),
+            (&[], Some("GPL-3.0"), true),
+            (&["MIT"], Some("MIT"), true),
+            (&["MIT"], Some("MIT OR Apache-2.0"), true),
+            (&["MIT"], Some("MIT AND Apache-2.0"), false),
+            (&["MIT", "Apache-2.0"], Some("MIT AND Apache-2.0"), true),
+            (&["MIT"], Some("MIT/Apache-2.0"), true),
+            (
+                &["Apache-2.0"],
+                Some("(MIT OR Apache-2.0) AND Unicode-DFS-2016"),
+                false,
+            ),
+            (&["MIT"], Some("GPL-3.0-only"), false),
+            (&["MIT"], Some("not a license"), false),
+            (&["MIT"], None, false),
+            (&["MTI"], Some("MIT"), false),
+        ];
+        for (allowed, license, expected) in cases {
+            assert_eq!(
+                policy(allowed).allows(license),
+                expected,
+                "{license:?} with {allowed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn violation() {
+        let policy = policy(&["MIT"]);
+        assert_eq!(policy.violation("serde", Some("MIT OR Apache-2.0")), None);
+        assert_eq!(
+            policy.violation("gpl", Some("GPL-3.0")).as_deref(),
+            Some("gpl is licensed under GPL-3.0, which the license policy doesn't allow")
+        );
+        assert_eq!(
+            policy.violation("mystery", None).as_deref(),
+            Some("mystery is licensed under an unknown license, which the license policy doesn't allow")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(policy(&["MIT", "Apache-2.0", "GPL-3.0"])
+            .errors()
+            .is_empty());
+        assert_eq!(
+            policy(&["MIT", "MTI", ""]).errors(),
+            [
+                "Ignoring 'MTI' in allowed_licenses, unknown term",
+                "Ignoring '' in allowed_licenses, empty expression"
+            ]
+        );
+    }
+}
@@ -0,0 +1,490 @@
+use crate::config::{config_dir, Appearance, Colors, Palette};
+use anyhow::{anyhow, Error, Result};
+use serde::Deserialize;
+use std::{env, fs};
+use tui::style::{Color, Modifier, Style};
+
+pub const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "high-contrast"];
+
+/// Built-in themes followed by the files in the themes directory
+pub fn theme_names() -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|n| n.to_string()).collect();
+    if let Ok(entries) = config_dir().and_then(|dir| Ok(fs::read_dir(dir.join("themes"))?)) {
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().is_some_and(|e| e == "toml") {
+                if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Resolved styles for every element of the UI
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub title: Style,
+    pub tab: Style,
+    pub selected_tab: Style,
+    pub border: Style,
+    pub text: Style,
+    pub heading: Style,
+    pub dim_text: Style,
+    pub selection: Style,
+    pub input: Style,
+    pub key: Style,
+    pub warning: Style,
+    pub error: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        ThemeFile::dark().resolve(Palette::Truecolor)
+    }
+}
+
+impl Theme {
+    /// Loads a built-in theme or `<config dir>/themes/<name>.toml`, with any
+    /// colours from the old `[colors]` section over it
+    pub fn load(appearance: &Appearance, colors: Option<&Colors>) -> Result<Self> {
+        let file = match appearance.theme.as_str() {
+            "dark" => ThemeFile::dark(),
+            "light" => ThemeFile::light(),
+            "high-contrast" => ThemeFile::high_contrast(),
+            name => {
+                let mut path = config_dir()?;
+                path.push("themes");
+                path.push(format!("{name}.toml"));
+                let text = fs::read_to_string(&path)
+                    .map_err(|e| anyhow!("Unable to read theme {}: {e}", path.display()))?;
+                toml::from_str(&text)
+                    .map_err(|e| anyhow!("Unable to load theme {}: {e}", path.display()))?
+            }
+        };
+        let file = match colors {
+            Some(colors) => file.with_colors(colors),
+            None => file,
+        };
+        Ok(file.resolve(appearance.palette))
+    }
+
+    /// Background for the title while a warning or error is flashing
+    pub fn flash(&self, style: Style) -> Style {
+        self.title.bg(style.fg.unwrap_or(Color::Reset))
+    }
+}
+
+/// A theme as written in a theme file, missing elements fall back to the dark theme
+#[derive(Deserialize)]
+#[serde(default)]
+struct ThemeFile {
+    title: Element,
+    tab: Element,
+    selected_tab: Element,
+    border: Element,
+    text: Element,
+    heading: Element,
+    dim_text: Element,
+    selection: Element,
+    input: Element,
+    key: Element,
+    warning: Element,
+    error: Element,
+}
+
+impl Default for ThemeFile {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl ThemeFile {
+    fn dark() -> Self {
+        let primary = ThemeColor(Color::Rgb(238, 111, 248));
+        Self {
+            title: Element::bg(primary).bold(),
+            tab: Element::default().dim(),
+            selected_tab: Element::default().bold(),
+            border: Element::default(),
+            text: Element::default(),
+            heading: Element::default().bold(),
+            dim_text: Element::default().dim(),
+            selection: Element::fg(primary),
+            input: Element::fg(primary),
+            key: Element::default().bold().dim(),
+            warning: Element::fg(ThemeColor(Color::Rgb(230, 126, 34))),
+            error: Element::fg(ThemeColor(Color::Rgb(255, 3, 3))),
+        }
+    }
+    fn light() -> Self {
+        let primary = ThemeColor(Color::Rgb(142, 36, 170));
+        Self {
+            title: Element::bg(primary).bold(),
+            selection: Element::fg(primary),
+            input: Element::fg(primary),
+            text: Element::fg(ThemeColor(Color::Rgb(30, 30, 30))),
+            heading: Element::fg(ThemeColor(Color::Rgb(30, 30, 30))).bold(),
+            dim_text: Element::fg(ThemeColor(Color::Rgb(110, 110, 110))),
+            key: Element::fg(ThemeColor(Color::Rgb(60, 60, 60))).bold(),
+            warning: Element::fg(ThemeColor(Color::Rgb(191, 87, 0))),
+            error: Element::fg(ThemeColor(Color::Rgb(200, 0, 0))),
+            ..Self::dark()
+        }
+    }
+    fn high_contrast() -> Self {
+        let yellow = ThemeColor(Color::Yellow);
+        Self {
+            title: Element::bg(yellow).bold(),
+            tab: Element::fg(ThemeColor(Color::Gray)),
+            selected_tab: Element::fg(yellow).bold(),
+            border: Element::fg(ThemeColor(Color::White)),
+            text: Element::fg(ThemeColor(Color::White)),
+            heading: Element::fg(ThemeColor(Color::White)).bold(),
+            dim_text: Element::fg(ThemeColor(Color::Gray)),
+            selection: Element::fg(yellow).bold(),
+            input: Element::fg(yellow),
+            key: Element::fg(ThemeColor(Color::Cyan)).bold(),
+            warning: Element::fg(ThemeColor(Color::LightYellow)),
+            error: Element::fg(ThemeColor(Color::LightRed)),
+        }
+    }
+
+    /// Colours left at their old defaults are skipped, they're what every
+    /// config file used to be written with and would hide the theme's own
+    fn with_colors(mut self, colors: &Colors) -> Self {
+        let changed = |color: Option<[u8; 3]>, default: [u8; 3]| {
+            color
+                .filter(|c| *c != default)
+                .map(|[r, g, b]| ThemeColor(Color::Rgb(r, g, b)))
+        };
+        if let Some(primary) = changed(colors.primary, [238, 111, 248]) {
+            self.title.bg = Some(primary);
+            self.selection.fg = Some(primary);
+            self.input.fg = Some(primary);
+        }
+        if let Some(warn) = changed(colors.warn, [230, 126, 34]) {
+            self.warning.fg = Some(warn);
+        }
+        if let Some(error) = changed(colors.error, [255, 3, 3]) {
+            self.error.fg = Some(error);
+        }
+        self
+    }
+
+    fn resolve(self, palette: Palette) -> Theme {
+        let palette = match palette {
+            Palette::Auto => detect_palette(),
+            palette => palette,
+        };
+        Theme {
+            title: self.title.style(palette),
+            tab: self.tab.style(palette),
+            selected_tab: self.selected_tab.style(palette),
+            border: self.border.style(palette),
+            text: self.text.style(palette),
+            heading: self.heading.style(palette),
+            dim_text: self.dim_text.style(palette),
+            selection: self.selection.style(palette),
+            input: self.input.style(palette),
+            key: self.key.style(palette),
+            warning: self.warning.style(palette),
+            error: self.error.style(palette),
+        }
+    }
+}
+
+#[derive(Default, Clone, Deserialize)]
+#[serde(default)]
+struct Element {
+    fg: Option<ThemeColor>,
+    bg: Option<ThemeColor>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underlined: bool,
+}
+
+impl Element {
+    fn fg(color: ThemeColor) -> Self {
+        Self {
+            fg: Some(color),
+            ..Default::default()
+        }
+    }
+    fn bg(color: ThemeColor) -> Self {
+        Self {
+            bg: Some(color),
+            ..Default::default()
+        }
+    }
+    fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+    fn dim(self) -> Self {
+        Self { dim: true, ..self }
+    }
+
+    fn style(&self, palette: Palette) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg.convert(palette));
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg.convert(palette));
+        }
+        for (enabled, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.dim, Modifier::DIM),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+        ] {
+            if enabled {
+                style = style.add_modifier(modifier);
+            }
+        }
+        style
+    }
+}
+
+/// A colour written as `#rrggbb`, a 256 colour index or a name like `light-red`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(|| anyhow!("Invalid hex colour '{s}'"))
+            };
+            if hex.len() != 6 {
+                return Err(anyhow!("Invalid hex colour '{s}'"));
+            }
+            return Ok(Self(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)));
+        }
+        if let Ok(index) = s.parse() {
+            return Ok(Self(Color::Indexed(index)));
+        }
+        let color = match s.to_lowercase().replace('_', "-").as_str() {
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "dark-gray" | "dark-grey" => Color::DarkGray,
+            "light-red" => Color::LightRed,
+            "light-green" => Color::LightGreen,
+            "light-yellow" => Color::LightYellow,
+            "light-blue" => Color::LightBlue,
+            "light-magenta" => Color::LightMagenta,
+            "light-cyan" => Color::LightCyan,
+            "white" => Color::White,
+            _ => return Err(anyhow!("Unknown colour '{s}'")),
+        };
+        Ok(Self(color))
+    }
+}
+
+impl ThemeColor {
+    /// Downgrades rgb colours for terminals without truecolor support
+    fn convert(self, palette: Palette) -> Color {
+        match (self.0, palette) {
+            (Color::Rgb(r, g, b), Palette::Ansi256) => Color::Indexed(rgb_to_256(r, g, b)),
+            (Color::Rgb(r, g, b), Palette::Ansi16) => rgb_to_16(r, g, b),
+            (color, _) => color,
+        }
+    }
+}
+
+fn detect_palette() -> Palette {
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    if colorterm.contains("truecolor") || colorterm.contains("24bit") {
+        return Palette::Truecolor;
+    }
+    if env::var("TERM").unwrap_or_default().contains("256") {
+        return Palette::Ansi256;
+    }
+    Palette::Ansi16
+}
+
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    // The colour cube's levels aren't evenly spaced
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..LEVELS.len())
+            .min_by_key(|&i| LEVELS[i].abs_diff(v))
+            .unwrap_or_default()
+    };
+    let distance = |c: [u8; 3]| {
+        [(r, c[0]), (g, c[1]), (b, c[2])]
+            .iter()
+            .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+            .sum::<i32>()
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = 16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8;
+    let cube_distance = distance([LEVELS[ri], LEVELS[gi], LEVELS[bi]]);
+
+    // Greys from 8 to 238 in steps of 10 are finer than the cube for colours near grey
+    let average = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+    let grey = (average.saturating_sub(3) / 10).min(23);
+    let level = 8 + 10 * grey;
+    match distance([level; 3]) < cube_distance {
+        true => 232 + grey,
+        false => cube,
+    }
+}
+
+fn rgb_to_16(r: u8, g: u8, b: u8) -> Color {
+    const ANSI: [(Color, [u8; 3]); 16] = [
+        (Color::Black, [0, 0, 0]),
+        (Color::Red, [128, 0, 0]),
+        (Color::Green, [0, 128, 0]),
+        (Color::Yellow, [128, 128, 0]),
+        (Color::Blue, [0, 0, 128]),
+        (Color::Magenta, [128, 0, 128]),
+        (Color::Cyan, [0, 128, 128]),
+        (Color::Gray, [192, 192, 192]),
+        (Color::DarkGray, [128, 128, 128]),
+        (Color::LightRed, [255, 0, 0]),
+        (Color::LightGreen, [0, 255, 0]),
+        (Color::LightYellow, [255, 255, 0]),
+        (Color::LightBlue, [0, 0, 255]),
+        (Color::LightMagenta, [255, 0, 255]),
+        (Color::LightCyan, [0, 255, 255]),
+        (Color::White, [255, 255, 255]),
+    ];
+    let distance = |c: [u8; 3]| {
+        [(r, c[0]), (g, c[1]), (b, c[2])]
+            .iter()
+            .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+            .sum::<i32>()
+    };
+    ANSI.iter()
+        .min_by_key(|(_, c)| distance(*c))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colours() {
+        let cases = [
+            ("#ff0080", Some(Color::Rgb(255, 0, 128))),
+            ("#FF0080", Some(Color::Rgb(255, 0, 128))),
+            ("#000000", Some(Color::Rgb(0, 0, 0))),
+            ("42", Some(Color::Indexed(42))),
+            ("255", Some(Color::Indexed(255))),
+            ("red", Some(Color::Red)),
+            ("Light-Red", Some(Color::LightRed)),
+            ("light_red", Some(Color::LightRed)),
+            ("grey", Some(Color::Gray)),
+            ("dark-grey", Some(Color::DarkGray)),
+            ("default", Some(Color::Reset)),
+            // Invalid
+            ("#ff008", None),
+            ("#ff00800", None),
+            ("#gg0000", None),
+            ("#ffé00", None),
+            ("ff0080", None),
+            ("256", None),
+            ("-1", None),
+            ("purple", None),
+            ("", None),
+        ];
+        for (s, expected) in cases {
+            let color = ThemeColor::try_from(s.to_owned()).ok().map(|c| c.0);
+            assert_eq!(color, expected, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn converts_to_256_colours() {
+        let cases = [
+            // Greys use whichever of the ramp and the cube is closer
+            ((0, 0, 0), 16),
+            ((7, 7, 7), 232),
+            ((8, 8, 8), 232),
+            ((128, 128, 128), 244),
+            ((175, 175, 175), 145),
+            ((248, 248, 248), 231),
+            ((255, 255, 255), 231),
+            // Everything else is the nearest point in the 6x6x6 cube
+            ((255, 0, 0), 196),
+            ((0, 255, 0), 46),
+            ((0, 0, 255), 21),
+            ((255, 3, 3), 196),
+            ((0, 95, 135), 24),
+            ((238, 111, 248), 207),
+            ((230, 126, 34), 172),
+        ];
+        for ((r, g, b), expected) in cases {
+            assert_eq!(rgb_to_256(r, g, b), expected, "{r} {g} {b}");
+        }
+    }
+
+    #[test]
+    fn converts_to_16_colours() {
+        let cases = [
+            ((0, 0, 0), Color::Black),
+            ((250, 250, 250), Color::White),
+            ((100, 100, 100), Color::DarkGray),
+            ((200, 200, 200), Color::Gray),
+            ((100, 0, 0), Color::Red),
+            ((200, 0, 0), Color::LightRed),
+            ((255, 3, 3), Color::LightRed),
+            ((230, 126, 34), Color::Yellow),
+            ((0, 200, 200), Color::LightCyan),
+            ((0, 0, 140), Color::Blue),
+        ];
+        for ((r, g, b), expected) in cases {
+            assert_eq!(rgb_to_16(r, g, b), expected, "{r} {g} {b}");
+        }
+    }
+
+    #[test]
+    fn downgrades_only_rgb() {
+        let rgb = ThemeColor(Color::Rgb(255, 0, 0));
+        assert_eq!(rgb.convert(Palette::Truecolor), Color::Rgb(255, 0, 0));
+        assert_eq!(rgb.convert(Palette::Ansi256), Color::Indexed(196));
+        assert_eq!(rgb.convert(Palette::Ansi16), Color::LightRed);
+        let named = ThemeColor(Color::Cyan);
+        assert_eq!(named.convert(Palette::Ansi16), Color::Cyan);
+    }
+
+    #[test]
+    fn old_colors_go_over_the_theme() {
+        let colors = Colors {
+            primary: Some([1, 2, 3]),
+            // The old defaults
+            warn: Some([230, 126, 34]),
+            error: Some([255, 3, 3]),
+            ..Default::default()
+        };
+        let theme = ThemeFile::light()
+            .with_colors(&colors)
+            .resolve(Palette::Truecolor);
+        let light = ThemeFile::light().resolve(Palette::Truecolor);
+        assert_eq!(theme.title.bg, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(theme.selection.fg, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(theme.input.fg, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(theme.warning, light.warning);
+        assert_eq!(theme.error, light.error);
+
+        let config: crate::config::Config =
+            toml::from_str("[colors]\nprimary = [1, 2, 3]\n").unwrap();
+        assert_eq!(config.colors.and_then(|c| c.primary), Some([1, 2, 3]));
+    }
+}
@@ -1,16 +1,21 @@
 use crate::{
     app::App,
-    pages::{manage, search},
+    pages::{
+        command_line, compare, debug, details, favourites, help, manage, preview, search, versions,
+    },
 };
 use tui::{
     backend::Backend,
-    layout::{Constraint, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
     text::{Span, Text},
     widgets::{Paragraph, Tabs},
     Frame,
 };
 
+pub const TAB_TITLES: [&str; 3] = ["search", "manage", "favourites"];
+const SPINNER: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+
 pub fn draw_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     let chunks = Layout::default()
         .constraints([
@@ -25,8 +30,23 @@ pub fn draw_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     match app.tab {
         0 => search::update(f, app, chunks[2]),
         1 => manage::update(f, app, chunks[2]),
+        2 => favourites::update(f, app, chunks[2]),
         _ => {}
     }
+
+    let size = f.size();
+    details::update(f, app, chunks[2]);
+    compare::update(f, app, chunks[2]);
+    versions::update(f, app, chunks[2]);
+    preview::update(f, app, chunks[2]);
+    debug::update(f, app, chunks[2]);
+
+    let bottom_row = Rect::new(size.x, size.bottom().saturating_sub(1), size.width, 1);
+    command_line::update(f, app, bottom_row);
+
+    if app.help_page.open {
+        help::update(f, app, size);
+    }
 }
 
 fn draw_header<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
@@ -34,13 +54,12 @@ fn draw_header<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
         .constraints([Constraint::Length(2), Constraint::Length(1)])
         .split(area);
 
-    // Title
-    let title = Text::styled(
-        " cratui ",
-        Style::default()
-            .bg(app.primary_color())
-            .add_modifier(Modifier::BOLD),
-    );
+    // Title, with a spinner while anything loads in the background
+    let title = match app.worker.is_busy() {
+        true => format!(" cratui {} ", SPINNER[app.ticks % SPINNER.len()]),
+        false => " cratui ".to_owned(),
+    };
+    let title = Text::styled(title, app.title_style());
     f.render_widget(
         Paragraph::new(title),
         Layout::default()
@@ -50,28 +69,47 @@ fn draw_header<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     );
 
     // Tabs
-    let tabs = Tabs::new(vec![
-        Span::raw("search").into(),
-        Span::raw("manage").into(),
-        Span::raw("favourites").into(),
-    ])
-    .style(Style::default().add_modifier(Modifier::DIM))
-    .highlight_style(
-        Style::default()
-            .add_modifier(Modifier::BOLD)
-            .remove_modifier(Modifier::DIM),
-    )
-    .divider("│")
-    .select(app.tab as usize);
-    f.render_widget(
-        tabs,
-        Layout::default()
-            .constraints([Constraint::Length(1)])
-            .horizontal_margin(2)
-            .split(chunks[1])[0],
-    );
+    let tabs_area = Layout::default()
+        .constraints([Constraint::Length(1)])
+        .horizontal_margin(2)
+        .split(chunks[1])[0];
+    let tabs = Tabs::new(TAB_TITLES.iter().map(|t| Span::raw(*t).into()).collect())
+        .style(app.theme.tab)
+        // Reset first so the selected tab doesn't inherit the unselected modifiers
+        .highlight_style(Style::reset().patch(app.theme.selected_tab))
+        .divider("│")
+        .select(app.tab as usize);
+    f.render_widget(tabs, tabs_area);
+
+    // Each title is padded by a space on both sides and followed by the divider
+    let mut x = tabs_area.x;
+    app.tab_rects = TAB_TITLES
+        .iter()
+        .map(|title| {
+            let width = title.chars().count() as u16 + 2;
+            let rect = Rect::new(x, tabs_area.y, width, 1).intersection(tabs_area);
+            x += width + 1;
+            rect
+        })
+        .collect();
 }
 
-pub fn rgb(rgb: [u8; 3]) -> Color {
-    Color::Rgb(rgb[0], rgb[1], rgb[2])
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    let vertical = Layout::default()
+        .constraints([
+            Constraint::Length((area.height - height) / 2),
+            Constraint::Length(height),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length((area.width - width) / 2),
+            Constraint::Length(width),
+            Constraint::Min(0),
+        ])
+        .split(vertical[1])[1]
 }
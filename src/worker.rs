@@ -0,0 +1,297 @@
+use crate::{
+    advisories::{self, AdvisoryDb},
+    cargo::{self, Duplicate, InfoResponse, Metadata, SearchResponse, Sort},
+    config::Advisories,
+    pages::compare::Column,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::{HashMap, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+/// How many requests can run at once
+const THREADS: usize = 4;
+/// How many prefetches can run at once, crates.io only takes a request a second
+/// so more would hold up searches
+const MAX_BACKGROUND: usize = 1;
+
+/// Work that's done off the UI thread, identical requests are only run once at a time
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Request {
+    Search {
+        query: String,
+        page: usize,
+        limit: usize,
+        sort: Sort,
+    },
+    Info(String),
+    Compare(Vec<String>),
+    Metadata,
+    Duplicates,
+    /// Scans the project's code for dependencies it never refers to
+    Unused,
+    /// Reads the advisory database at the configured path
+    LoadAdvisories(Option<PathBuf>),
+    /// Clones or pulls the advisory database at the configured path
+    RefreshAdvisories(Option<PathBuf>),
+}
+
+/// The answer to each kind of `Request`
+#[derive(Debug)]
+pub enum Response {
+    Search(SearchResponse),
+    Info(Box<InfoResponse>),
+    /// A column for each crate, failing separately
+    Compare(Vec<Result<Column>>),
+    Metadata(Metadata),
+    Duplicates(Vec<Duplicate>),
+    Unused(Vec<String>),
+    Advisories(AdvisoryDb),
+}
+
+struct Job {
+    id: u64,
+    request: Request,
+    cancelled: Arc<AtomicBool>,
+}
+
+type Reply = (u64, Request, Result<Response>);
+
+/// Jobs waiting for a thread, prefetching only gets a thread when nothing else wants one
+#[derive(Default)]
+struct Queue {
+    urgent: VecDeque<Job>,
+    background: VecDeque<Job>,
+    /// Background jobs running now
+    running_background: usize,
+    closed: bool,
+}
+
+impl Queue {
+    /// The next job to run and whether it's a background one
+    fn pop(&mut self) -> Option<(Job, bool)> {
+        if let Some(job) = self.urgent.pop_front() {
+            return Some((job, false));
+        }
+        if self.running_background < MAX_BACKGROUND {
+            if let Some(job) = self.background.pop_front() {
+                self.running_background += 1;
+                return Some((job, true));
+            }
+        }
+        None
+    }
+}
+
+type SharedQueue = Arc<(Mutex<Queue>, Condvar)>;
+
+/// Runs requests on a pool of threads and hands back their responses
+pub struct Worker {
+    queue: SharedQueue,
+    replies: Receiver<Reply>,
+    /// Requests that haven't been answered, by the id of the job running them
+    pending: HashMap<Request, (u64, Arc<AtomicBool>)>,
+    next_id: u64,
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        let queue = SharedQueue::default();
+        let (reply_sender, replies) = mpsc::channel();
+        for _ in 0..THREADS {
+            let queue = queue.clone();
+            let reply_sender = reply_sender.clone();
+            thread::spawn(move || loop {
+                let Some((job, background)) = next_job(&queue) else {
+                    return;
+                };
+                let response = panic::catch_unwind(AssertUnwindSafe(|| run(&job.request)))
+                    .unwrap_or_else(|_| Err(anyhow!("Something went wrong loading this")));
+                if background {
+                    let (lock, ready) = &*queue;
+                    lock.lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .running_background -= 1;
+                    ready.notify_all();
+                }
+                if reply_sender.send((job.id, job.request, response)).is_err() {
+                    return;
+                }
+            });
+        }
+        Self {
+            queue,
+            replies,
+            pending: HashMap::new(),
+            next_id: 0,
+        }
+    }
+}
+
+/// Waits for a job that hasn't been cancelled, `None` once the worker is dropped
+fn next_job(queue: &SharedQueue) -> Option<(Job, bool)> {
+    let (lock, ready) = &**queue;
+    let mut queue = lock.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        if queue.closed {
+            return None;
+        }
+        match queue.pop() {
+            Some((job, background)) if job.cancelled.load(Ordering::Relaxed) => {
+                if background {
+                    queue.running_background -= 1;
+                }
+            }
+            Some(job) => return Some(job),
+            None => queue = ready.wait(queue).unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let (lock, ready) = &*self.queue;
+        lock.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        ready.notify_all();
+    }
+}
+
+impl std::fmt::Debug for Worker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Worker")
+            .field("pending", &self.pending.keys())
+            .finish()
+    }
+}
+
+impl Worker {
+    /// Starts a request ahead of any prefetching unless the same one is
+    /// already running, a prefetch of it that hasn't started is moved up
+    pub fn send(&mut self, request: Request) {
+        if let Some((id, _)) = self.pending.get(&request) {
+            let (lock, ready) = &*self.queue;
+            let mut queue = lock.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(i) = queue.background.iter().position(|job| job.id == *id) {
+                let job = queue.background.remove(i);
+                queue.urgent.extend(job);
+                ready.notify_one();
+            }
+            return;
+        }
+        self.start(request, false);
+    }
+
+    /// Makes these the only background requests waiting to run, any others
+    /// that haven't started are cancelled
+    pub fn prefetch(&mut self, requests: Vec<Request>) {
+        let stale: Vec<Request> = {
+            let (lock, _) = &*self.queue;
+            let queue = lock.lock().unwrap_or_else(|e| e.into_inner());
+            queue
+                .background
+                .iter()
+                .filter(|job| !requests.contains(&job.request))
+                // Skips jobs already cancelled, the request may have been sent again since
+                .filter(|job| {
+                    self.pending
+                        .get(&job.request)
+                        .is_some_and(|(id, _)| *id == job.id)
+                })
+                .map(|job| job.request.to_owned())
+                .collect()
+        };
+        for request in &stale {
+            self.cancel(request);
+        }
+        for request in requests {
+            if !self.pending.contains_key(&request) {
+                self.start(request, true);
+            }
+        }
+    }
+
+    fn start(&mut self, request: Request, background: bool) {
+        self.next_id += 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let job = Job {
+            id: self.next_id,
+            request: request.to_owned(),
+            cancelled: cancelled.clone(),
+        };
+        let (lock, ready) = &*self.queue;
+        let mut queue = lock.lock().unwrap_or_else(|e| e.into_inner());
+        match background {
+            true => queue.background.push_back(job),
+            false => queue.urgent.push_back(job),
+        }
+        ready.notify_one();
+        self.pending.insert(request, (self.next_id, cancelled));
+    }
+
+    /// Whether anything is still running
+    pub fn is_busy(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_pending(&self, request: &Request) -> bool {
+        self.pending.contains_key(request)
+    }
+
+    /// Stops a request if it hasn't started and throws away its response
+    pub fn cancel(&mut self, request: &Request) {
+        if let Some((_, cancelled)) = self.pending.remove(request) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Responses that have arrived since the last poll, cancelled requests are left out
+    pub fn poll(&mut self) -> Vec<(Request, Result<Response>)> {
+        let mut responses = vec![];
+        while let Ok((id, request, response)) = self.replies.try_recv() {
+            // A request cancelled and sent again has a new id
+            if self.pending.get(&request).is_some_and(|(i, _)| *i == id) {
+                self.pending.remove(&request);
+                responses.push((request, response));
+            }
+        }
+        responses
+    }
+}
+
+fn run(request: &Request) -> Result<Response> {
+    Ok(match request {
+        Request::Search {
+            query,
+            page,
+            limit,
+            sort,
+        } => Response::Search(cargo::search(query, *page, *limit, *sort)?),
+        Request::Info(id) => Response::Info(Box::new(cargo::info(id)?)),
+        Request::Compare(ids) => {
+            Response::Compare(ids.iter().map(|id| Column::fetch(id)).collect())
+        }
+        Request::Metadata => Response::Metadata(cargo::metadata()?),
+        Request::Duplicates => Response::Duplicates(cargo::duplicates()?),
+        Request::Unused => Response::Unused(cargo::unused_dependencies()?),
+        Request::LoadAdvisories(path) => Response::Advisories(AdvisoryDb::load(&Advisories {
+            path: path.to_owned(),
+        })?),
+        Request::RefreshAdvisories(path) => {
+            Response::Advisories(advisories::refresh(&Advisories {
+                path: path.to_owned(),
+            })?)
+        }
+    })
+}